@@ -0,0 +1,139 @@
+//! Throughput of `spsc::Channel` compared to a baseline without cache-padded and cached cursors
+//!
+//! Run with `cargo bench --bench spsc`
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fika::spsc::Channel;
+
+const MESSAGES: u32 = 10_000_000;
+const ROUNDS: usize = 5;
+
+fn main() {
+    bench::<64>();
+    bench::<100>();
+}
+
+fn bench<const N: usize>() {
+    let fika = best_of(|| {
+        let mut channel = Channel::<u32, N>::new();
+        let (sender, receiver) = channel.split();
+
+        run(
+            move |value| sender.send(value).is_ok(),
+            || receiver.recv().ok(),
+        )
+    });
+
+    let baseline = best_of(|| {
+        let channel = &baseline::Channel::<u32, N>::new();
+
+        run(
+            // SAFETY: only this closure sends
+            move |value| unsafe { channel.send(value) }.is_ok(),
+            // SAFETY: only this closure receives
+            || unsafe { channel.recv() },
+        )
+    });
+
+    report(N, "baseline", baseline);
+    report(N, "fika", fika);
+}
+
+fn run(send: impl Fn(u32) -> bool + Send, recv: impl Fn() -> Option<u32>) -> Duration {
+    thread::scope(|s| {
+        let start = Instant::now();
+
+        s.spawn(move || {
+            for value in 0..MESSAGES {
+                while !send(value) {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for expected in 0..MESSAGES {
+            loop {
+                if let Some(value) = recv() {
+                    assert_eq!(expected, value);
+                    break;
+                }
+                thread::yield_now();
+            }
+        }
+
+        start.elapsed()
+    })
+}
+
+fn best_of(mut f: impl FnMut() -> Duration) -> Duration {
+    (0..ROUNDS).map(|_| f()).min().unwrap()
+}
+
+fn report(capacity: usize, name: &str, elapsed: Duration) {
+    let throughput = f64::from(MESSAGES) / elapsed.as_secs_f64() / 1e6;
+    println!("N = {capacity:>3} {name:>8}: {elapsed:>12.3?} ({throughput:.1} Mmsg/s)");
+}
+
+/// The layout `spsc::Channel` had before its cursors were cache-padded and cached
+mod baseline {
+    use core::cell::UnsafeCell;
+    use core::mem::MaybeUninit;
+    use core::sync::atomic::{self, AtomicUsize};
+
+    pub struct Channel<T, const N: usize> {
+        read: AtomicUsize,
+        write: AtomicUsize,
+        buf: [UnsafeCell<MaybeUninit<T>>; N],
+    }
+
+    // SAFETY: callers of `send` and `recv` uphold the SPSC property
+    unsafe impl<T, const N: usize> Sync for Channel<T, N> where T: Send {}
+
+    impl<T, const N: usize> Channel<T, N> {
+        pub const fn new() -> Self {
+            Self {
+                read: AtomicUsize::new(0),
+                write: AtomicUsize::new(0),
+                buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            }
+        }
+
+        /// # Safety
+        /// - Caller must ensure that the SPSC property holds
+        pub unsafe fn send(&self, value: T) -> Result<(), T> {
+            let current_write = self.write.load(atomic::Ordering::Relaxed);
+            let acquired_read = self.read.load(atomic::Ordering::Acquire);
+            if current_write.wrapping_sub(acquired_read) == N {
+                return Err(value);
+            }
+
+            // SAFETY: SPSC and the fullness check ensure no data race with `recv`
+            unsafe {
+                self.buf[current_write % N].get().cast::<T>().write(value);
+            }
+            self.write
+                .store(current_write.wrapping_add(1), atomic::Ordering::Release);
+
+            Ok(())
+        }
+
+        /// # Safety
+        /// - Caller must ensure that the SPSC property holds
+        pub unsafe fn recv(&self) -> Option<T> {
+            let current_read = self.read.load(atomic::Ordering::Relaxed);
+            let acquired_write = self.write.load(atomic::Ordering::Acquire);
+            if current_read == acquired_write {
+                return None;
+            }
+
+            // SAFETY: SPSC and the emptiness check ensure no data race with `send`
+            let value = unsafe { self.buf[current_read % N].get().cast::<T>().read() };
+            self.read
+                .store(current_read.wrapping_add(1), atomic::Ordering::Release);
+
+            Some(value)
+        }
+    }
+}
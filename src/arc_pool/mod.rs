@@ -2,11 +2,12 @@
 //!
 //! Similar to the box pool but the "boxes" have the drop semantics of `std::sync::Arc`
 
-use core::mem::MaybeUninit;
+use core::mem::{ManuallyDrop, MaybeUninit};
 use core::sync::atomic::{self, AtomicUsize};
-use core::{fmt, ops};
+use core::{borrow, cmp, fmt, hash, ops, ptr};
 
 use crate::treiber::{self, OwningNodePtr, SharedNodePtr, Stack};
+use crate::vec::Storage;
 
 /// A pool of arcs
 pub struct ArcPool<T>
@@ -44,6 +45,22 @@ where
         }
     }
 
+    /// Requests a memory slot from the pool as a uniquely owned arc
+    ///
+    /// The contents of the returned handle can be mutated before it is converted into a shared
+    /// `Arc` with `UniqueArc::into_arc`
+    pub fn request_unique(&'static self, value: T) -> Result<UniqueArc<T>, T> {
+        if let Some(mut slot) = self.stack.pop() {
+            slot.data.write(value);
+
+            slot.strong_count.store(1, atomic::Ordering::Relaxed);
+
+            Ok(UniqueArc { inner: slot })
+        } else {
+            Err(value)
+        }
+    }
+
     /// Gives a memory slot to the pool
     pub fn manage(&'static self, slot: &'static mut Slot<T>) {
         slot.inner.data.stack = Some(&self.stack);
@@ -96,6 +113,29 @@ where
     inner: SharedNodePtr<Inner<T>>,
 }
 
+impl<T> Arc<T> {
+    /// Converts this arc into a uniquely owned arc if it is the only handle to its contents
+    ///
+    /// Returns the original arc if there are other handles
+    ///
+    /// This is an associated function so it does not shadow methods of `T`; use it as
+    /// `Arc::try_unique(arc)`
+    pub fn try_unique(this: Self) -> Result<UniqueArc<T>, Self> {
+        // Acquire: synchronizes with the Release `fetch_sub` in the `Drop` implementation of the
+        // other handles so their accesses to the contents happen before ours
+        if this.inner.strong_count.load(atomic::Ordering::Acquire) != 1 {
+            return Err(this);
+        }
+
+        let this = ManuallyDrop::new(this);
+        // SAFETY: as per the above check this is the only shared pointer left and it is not
+        // dropped
+        let inner = unsafe { this.inner.into_owning() };
+
+        Ok(UniqueArc { inner })
+    }
+}
+
 impl<T> Clone for Arc<T> {
     fn clone(&self) -> Self {
         const MAX_REFCOUNT: usize = isize::MAX as usize;
@@ -121,6 +161,42 @@ where
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for Arc<T>
+where
+    T: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        T::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T> ufmt::uDebug for Arc<T>
+where
+    T: ufmt::uDebug,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Arc<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        T::serialize(self, serializer)
+    }
+}
+
 impl<T> PartialEq for Arc<T>
 where
     T: PartialEq,
@@ -130,6 +206,65 @@ where
     }
 }
 
+impl<T> Eq for Arc<T> where T: Eq {}
+
+impl<T> PartialOrd for Arc<T>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<T> Ord for Arc<T>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
+impl<T> hash::Hash for Arc<T>
+where
+    T: hash::Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        T::hash(self, state)
+    }
+}
+
+impl<T> fmt::Display for Arc<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T> fmt::Pointer for Arc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&ptr::from_ref::<T>(self), f)
+    }
+}
+
+impl<T> borrow::Borrow<T> for Arc<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T> AsRef<T> for Arc<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
 impl<T> ops::Deref for Arc<T> {
     type Target = T;
 
@@ -158,13 +293,139 @@ impl<T> Drop for Arc<T> {
             // SAFETY: as per the above check this is the only shared pointer left
             let mut owning_ptr = unsafe { self.inner.into_owning() };
 
+            #[cfg(feature = "debug-pools")]
+            if !stack.check_release(&owning_ptr) {
+                return;
+            }
+
             // SAFETY: data is currently initialized and after we run the
             // destructor, `Box::deref*` cannot be used
             unsafe {
-                core::ptr::drop_in_place(owning_ptr.data.as_mut_ptr());
+                ptr::drop_in_place(owning_ptr.data.as_mut_ptr());
+            }
+            #[cfg(feature = "debug-pools")]
+            // SAFETY: the contents were destroyed above and the slot is not yet back in the pool
+            unsafe {
+                crate::debug_pools::poison(owning_ptr.data.as_mut_ptr());
+            }
+            // the slot was checked above
+            stack.push_unchecked(owning_ptr);
+        } else {
+            #[cfg(debug_assertions)]
+            unreachable!()
+        }
+    }
+}
+
+/// A uniquely owned arc managed by an `ArcPool`
+///
+/// Unlike `Arc`, it grants mutable access to its contents
+pub struct UniqueArc<T>
+where
+    T: 'static,
+{
+    inner: OwningNodePtr<Inner<T>>,
+}
+
+impl<T> UniqueArc<T> {
+    /// Converts this uniquely owned arc into a shared one
+    ///
+    /// This is an associated function so it does not shadow methods of `T`; use it as
+    /// `UniqueArc::into_arc(unique)`
+    pub fn into_arc(this: Self) -> Arc<T> {
+        let this = ManuallyDrop::new(this);
+        // SAFETY: the original handle is not dropped so it cannot be used after this operation
+        let inner = unsafe { this.inner.copy() };
+
+        Arc {
+            inner: inner.into_shared(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for UniqueArc<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for UniqueArc<T>
+where
+    T: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        T::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T> ufmt::uDebug for UniqueArc<T>
+where
+    T: ufmt::uDebug,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for UniqueArc<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        T::serialize(self, serializer)
+    }
+}
+
+impl<T> ops::Deref for UniqueArc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: while live, the arc contents are initialized
+        unsafe { &*self.inner.data.as_ptr() }
+    }
+}
+
+impl<T> ops::DerefMut for UniqueArc<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: while live, the arc contents are initialized
+        unsafe { &mut *self.inner.data.as_mut_ptr() }
+    }
+}
+
+impl<T> Drop for UniqueArc<T> {
+    fn drop(&mut self) {
+        if let Some(stack) = self.inner.stack {
+            #[cfg(feature = "debug-pools")]
+            if !stack.check_release(&self.inner) {
+                return;
+            }
+
+            // SAFETY: data is currently initialized and after we run the
+            // destructor, `UniqueArc::deref*` cannot be used
+            unsafe {
+                ptr::drop_in_place(self.inner.data.as_mut_ptr());
+            }
+            #[cfg(feature = "debug-pools")]
+            // SAFETY: the contents were destroyed above and the slot is not yet back in the pool
+            unsafe {
+                crate::debug_pools::poison(self.inner.data.as_mut_ptr());
             }
             // SAFETY: this is the destructor so the original pointer cannot be used by the caller
-            stack.push(owning_ptr);
+            let owning_ptr = unsafe { self.inner.copy() };
+            // the slot was checked above
+            stack.push_unchecked(owning_ptr);
         } else {
             #[cfg(debug_assertions)]
             unreachable!()
@@ -172,6 +433,29 @@ impl<T> Drop for Arc<T> {
     }
 }
 
+// SAFETY: the slot is never deallocated nor moved so moving the handle does not move `S`
+unsafe impl<S> Storage for UniqueArc<S>
+where
+    S: Storage,
+{
+    const INLINE: bool = false;
+
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        S::as_uninit_bytes(self)
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        S::as_uninit_bytes_mut(self)
+    }
+}
+
+// SAFETY: a unique arc has ownership semantics, like a box, so if the contents are Send then the
+// UniqueArc is also Send
+unsafe impl<T> Send for UniqueArc<T> where T: Send {}
+
+// SAFETY: the unique arc does not add synchronization of its own
+unsafe impl<T> Sync for UniqueArc<T> where T: Sync {}
+
 // SAFETY: moving an Arc between threads effectively copies a reference to its contents to
 // the receiver thread so the contents must be safe to share between threads (Sync). Furthermore,
 // to be compatible with API that moves out of a reference, e.g. `Option::take`, the contents must
@@ -252,6 +536,32 @@ mod tests {
         assert!(DESTROYED.load(atomic::Ordering::Relaxed));
     }
 
+    #[test]
+    fn unique_arc_round_trip() {
+        static POOL: ArcPool<i32> = ArcPool::new();
+
+        let slot = Box::leak(Box::new(Slot::new()));
+        POOL.manage(slot);
+
+        let mut unique = POOL.request_unique(41).ok().unwrap();
+        *unique += 1;
+
+        let arc = UniqueArc::into_arc(unique);
+        let arc2 = Arc::clone(&arc);
+        let arc = Arc::try_unique(arc).err().unwrap();
+        drop(arc2);
+
+        let unique = Arc::try_unique(arc).ok().unwrap();
+        assert_eq!(42, *unique);
+
+        assert!(POOL.request(0).is_err(), "expected pool to be exhausted");
+
+        // returns the slot to the pool
+        drop(unique);
+
+        assert!(POOL.request(0).is_ok(), "expected pool to have a slot");
+    }
+
     #[test]
     fn check_arc_is_send() {
         is_send::<Box<i32>>();
@@ -4,9 +4,11 @@
 //! destroyed when it goes out of scope
 
 use core::mem::MaybeUninit;
-use core::{fmt, ops};
+use core::ptr::NonNull;
+use core::{borrow, cmp, fmt, hash, ops, ptr};
 
 use crate::treiber::{self, OwningNodePtr, Stack};
+use crate::vec::Storage;
 
 /// A pool of boxes
 pub struct BoxPool<T>
@@ -80,6 +82,32 @@ where
     data: MaybeUninit<T>,
 }
 
+/// Returns an empty slot to its pool if dropped, e.g. when `T::clone` panics
+struct ReturnOnUnwind<T>
+where
+    T: 'static,
+{
+    stack: &'static Stack<Inner<T>>,
+    slot: Option<OwningNodePtr<Inner<T>>>,
+}
+
+impl<T> ReturnOnUnwind<T> {
+    fn into_slot(mut self) -> OwningNodePtr<Inner<T>> {
+        match self.slot.take() {
+            Some(slot) => slot,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T> Drop for ReturnOnUnwind<T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            self.stack.push(slot);
+        }
+    }
+}
+
 /// A boxed object managed by a `BoxPool`
 pub struct Box<T>
 where
@@ -88,6 +116,68 @@ where
     inner: OwningNodePtr<Inner<T>>,
 }
 
+impl<T> Box<T> {
+    /// Clones the contents of the box into a new slot requested from the same pool
+    ///
+    /// Returns `None` if the pool is exhausted
+    ///
+    /// This is an associated function so it does not shadow methods of `T`; use it as
+    /// `Box::try_clone(&boxed)`
+    pub fn try_clone(this: &Self) -> Option<Self>
+    where
+        T: Clone,
+    {
+        let stack = this.inner.stack?;
+        let guard = ReturnOnUnwind {
+            stack,
+            slot: Some(stack.pop()?),
+        };
+        let value = T::clone(this);
+
+        let mut slot = guard.into_slot();
+        slot.data.write(value);
+
+        Some(Box { inner: slot })
+    }
+
+    /// Projects the box into one of its parts, e.g. a field or a sub-slice
+    ///
+    /// The returned handle still owns the whole slot and returns it to the pool when dropped
+    ///
+    /// This is an associated function so it does not shadow methods of `T`; use it as
+    /// `Box::map(boxed, ..)`
+    pub fn map<U, F>(mut this: Self, f: F) -> MappedBox<T, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let projection = NonNull::from(f(&mut this));
+
+        MappedBox {
+            _boxed: this,
+            projection,
+        }
+    }
+
+    /// Fallible version of `Box::map`
+    ///
+    /// Returns the original box if the closure returns `None`
+    pub fn filter_map<U, F>(mut this: Self, f: F) -> Result<MappedBox<T, U>, Self>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        if let Some(projection) = f(&mut this).map(NonNull::from) {
+            Ok(MappedBox {
+                _boxed: this,
+                projection,
+            })
+        } else {
+            Err(this)
+        }
+    }
+}
+
 impl<T> fmt::Debug for Box<T>
 where
     T: fmt::Debug,
@@ -97,6 +187,42 @@ where
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for Box<T>
+where
+    T: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        T::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T> ufmt::uDebug for Box<T>
+where
+    T: ufmt::uDebug,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Box<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        T::serialize(self, serializer)
+    }
+}
+
 impl<T> PartialEq for Box<T>
 where
     T: PartialEq,
@@ -106,6 +232,77 @@ where
     }
 }
 
+impl<T> Eq for Box<T> where T: Eq {}
+
+impl<T> PartialOrd for Box<T>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<T> Ord for Box<T>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
+impl<T> hash::Hash for Box<T>
+where
+    T: hash::Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        T::hash(self, state)
+    }
+}
+
+impl<T> fmt::Display for Box<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T> fmt::Pointer for Box<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&ptr::from_ref::<T>(self), f)
+    }
+}
+
+impl<T> borrow::Borrow<T> for Box<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T> borrow::BorrowMut<T> for Box<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T> AsRef<T> for Box<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> AsMut<T> for Box<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
 impl<T> ops::Deref for Box<T> {
     type Target = T;
 
@@ -125,14 +322,25 @@ impl<T> ops::DerefMut for Box<T> {
 impl<T> Drop for Box<T> {
     fn drop(&mut self) {
         if let Some(stack) = self.inner.stack {
+            #[cfg(feature = "debug-pools")]
+            if !stack.check_release(&self.inner) {
+                return;
+            }
+
             // SAFETY: data is currently initialized and after we run the
             // destructor, `Box::deref*` cannot be used
             unsafe {
-                core::ptr::drop_in_place(self.inner.data.as_mut_ptr());
+                ptr::drop_in_place(self.inner.data.as_mut_ptr());
+            }
+            #[cfg(feature = "debug-pools")]
+            // SAFETY: the contents were destroyed above and the slot is not yet back in the pool
+            unsafe {
+                crate::debug_pools::poison(self.inner.data.as_mut_ptr());
             }
             // SAFETY: this is the destructor so the original pointer cannot be used by the caller
             let owning_ptr = unsafe { self.inner.copy() };
-            stack.push(owning_ptr);
+            // the slot was checked above
+            stack.push_unchecked(owning_ptr);
         } else {
             #[cfg(debug_assertions)]
             unreachable!()
@@ -140,6 +348,22 @@ impl<T> Drop for Box<T> {
     }
 }
 
+// SAFETY: the slot is never deallocated nor moved so moving the box does not move `S`
+unsafe impl<S> Storage for Box<S>
+where
+    S: Storage,
+{
+    const INLINE: bool = false;
+
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        S::as_uninit_bytes(self)
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        S::as_uninit_bytes_mut(self)
+    }
+}
+
 // SAFETY: moving a box transfers ownership so if the contents are Send then the Box is also Send
 unsafe impl<T> Send for Box<T> where T: Send {}
 
@@ -147,6 +371,104 @@ unsafe impl<T> Send for Box<T> where T: Send {}
 // the box does not add synchronization of its own
 unsafe impl<T> Sync for Box<T> where T: Sync {}
 
+/// A box projected into one of its parts
+///
+/// Created with `Box::map` or `Box::filter_map`. Dereferences to the projected part `U` but owns
+/// the whole `T`, which is destroyed and returned to the pool when this handle goes out of scope
+pub struct MappedBox<T, U>
+where
+    T: 'static,
+    U: ?Sized,
+{
+    // only held to return the slot to the pool on drop
+    _boxed: Box<T>,
+    projection: NonNull<U>,
+}
+
+impl<T, U> fmt::Debug for MappedBox<T, U>
+where
+    U: fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        U::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T, U> defmt::Format for MappedBox<T, U>
+where
+    U: defmt::Format + ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        U::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T, U> ufmt::uDebug for MappedBox<T, U>
+where
+    U: ufmt::uDebug + ?Sized,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        U::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, U> serde::Serialize for MappedBox<T, U>
+where
+    U: serde::Serialize + ?Sized,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        U::serialize(self, serializer)
+    }
+}
+
+impl<T, U> ops::Deref for MappedBox<T, U>
+where
+    U: ?Sized,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `projection` points into the box contents, which are initialized and never
+        // moved while `_boxed` is live. `_boxed` is not dereferenced while the projection exists
+        unsafe { self.projection.as_ref() }
+    }
+}
+
+impl<T, U> ops::DerefMut for MappedBox<T, U>
+where
+    U: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: see `Deref` implementation. `projection` was derived from a unique borrow
+        unsafe { self.projection.as_mut() }
+    }
+}
+
+// SAFETY: the handle owns the whole box and gives unique access to the projected part
+unsafe impl<T, U> Send for MappedBox<T, U>
+where
+    T: Send,
+    U: Send + ?Sized,
+{
+}
+
+// SAFETY: the handle only hands out shared references to the projected part from `&self`
+unsafe impl<T, U> Sync for MappedBox<T, U>
+where
+    T: Sync,
+    U: Sync + ?Sized,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +535,171 @@ mod tests {
         assert!(DESTROYED.load(atomic::Ordering::Relaxed));
     }
 
+    #[cfg(feature = "debug-pools")]
+    #[test]
+    fn double_free_does_not_run_the_destructor_twice() {
+        use core::sync::atomic::AtomicUsize;
+
+        use crate::debug_pools::{self, Violation};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        static DOUBLE_FREES: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+        }
+
+        static POOL: BoxPool<Counted> = BoxPool::new();
+
+        let _guard = debug_pools::TEST_HOOK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        debug_pools::set_hook(|violation| {
+            if let Violation::DoubleFree { .. } = violation {
+                DOUBLE_FREES.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+        });
+
+        let slot = StdBox::leak(StdBox::new(Slot::new()));
+        POOL.manage(slot);
+
+        let boxed = POOL.request(Counted).ok().unwrap();
+        // SAFETY: deliberate misuse
+        let alias = unsafe { core::ptr::read(&boxed) };
+        drop(boxed);
+        drop(alias);
+
+        assert_eq!(1, DROPS.load(atomic::Ordering::Relaxed));
+        assert_eq!(1, DOUBLE_FREES.load(atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    fn map_projects_and_returns_slot_on_drop() {
+        struct Message {
+            header: u32,
+            payload: [u8; 4],
+        }
+
+        static POOL: BoxPool<Message> = BoxPool::new();
+
+        let slot = StdBox::leak(StdBox::new(Slot::new()));
+        POOL.manage(slot);
+
+        let message = Message {
+            header: 0,
+            payload: [1, 2, 3, 4],
+        };
+        let boxed = POOL.request(message).ok().unwrap();
+
+        let mut tail = Box::map(boxed, |message| &mut message.payload[2..]);
+        assert_eq!([3, 4], *tail);
+
+        tail[0] = 0;
+        assert_eq!([0, 4], *tail);
+
+        let message = Message {
+            header: 1,
+            payload: [0; 4],
+        };
+        let Err(message) = POOL.request(message) else {
+            panic!("expected pool to be exhausted")
+        };
+
+        // returns the whole slot to the pool
+        drop(tail);
+
+        let boxed = POOL.request(message).ok().unwrap();
+        assert_eq!(1, boxed.header);
+    }
+
+    #[test]
+    fn filter_map_returns_original_box() {
+        static POOL: BoxPool<Option<i32>> = BoxPool::new();
+
+        let slot = StdBox::leak(StdBox::new(Slot::new()));
+        POOL.manage(slot);
+
+        let boxed = POOL.request(None).ok().unwrap();
+        let boxed = Box::filter_map(boxed, Option::as_mut).err().unwrap();
+        assert_eq!(None, *boxed);
+        drop(boxed);
+
+        let boxed = POOL.request(Some(42)).ok().unwrap();
+        let value = Box::filter_map(boxed, Option::as_mut).ok().unwrap();
+        assert_eq!(42, *value);
+    }
+
+    #[test]
+    fn try_clone_uses_a_slot_from_the_same_pool() {
+        static POOL: BoxPool<i32> = BoxPool::new();
+
+        for _ in 0..2 {
+            let slot = StdBox::leak(StdBox::new(Slot::new()));
+            POOL.manage(slot);
+        }
+
+        let value = 42;
+        let boxed = POOL.request(value).ok().unwrap();
+        let clone = Box::try_clone(&boxed).unwrap();
+        assert_eq!(boxed, clone);
+
+        assert!(
+            Box::try_clone(&boxed).is_none(),
+            "expected pool to be exhausted"
+        );
+    }
+
+    #[test]
+    fn try_clone_returns_the_slot_if_clone_panics() {
+        struct Unclonable;
+
+        impl Clone for Unclonable {
+            fn clone(&self) -> Self {
+                panic!("cannot clone")
+            }
+        }
+
+        static POOL: BoxPool<Unclonable> = BoxPool::new();
+
+        for _ in 0..2 {
+            let slot = StdBox::leak(StdBox::new(Slot::new()));
+            POOL.manage(slot);
+        }
+
+        let boxed = POOL.request(Unclonable).ok().unwrap();
+        let outcome =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| Box::try_clone(&boxed)));
+        assert!(outcome.is_err());
+
+        assert!(POOL.request(Unclonable).is_ok());
+    }
+
+    #[test]
+    fn handles_are_ordered_and_hashed_like_their_contents() {
+        use core::hash::BuildHasher;
+        use std::hash::RandomState;
+
+        static POOL: BoxPool<i32> = BoxPool::new();
+
+        for _ in 0..2 {
+            let slot = StdBox::leak(StdBox::new(Slot::new()));
+            POOL.manage(slot);
+        }
+
+        let small = POOL.request(1).ok().unwrap();
+        let big = POOL.request(2).ok().unwrap();
+        assert!(small < big);
+        assert_eq!(cmp::Ordering::Greater, big.cmp(&small));
+        assert_eq!("1", format!("{small}"));
+
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(1), state.hash_one(&small));
+    }
+
     #[test]
     fn check_box_is_send() {
         is_send::<Box<i32>>();
@@ -223,6 +710,16 @@ mod tests {
         is_sync::<Box<i32>>();
     }
 
+    #[test]
+    fn check_mapped_box_is_send() {
+        is_send::<MappedBox<[i32; 2], [i32]>>();
+    }
+
+    #[test]
+    fn check_mapped_box_is_sync() {
+        is_sync::<MappedBox<[i32; 2], [i32]>>();
+    }
+
     fn is_send<T>()
     where
         T: Send,
@@ -0,0 +1,440 @@
+//! A fixed-capacity, single-producer, multi-consumer broadcast channel
+//!
+//! Every subscriber receives a clone of every value, at its own pace. The publisher never waits
+//! for subscribers: it overwrites the oldest value when the channel is full and subscribers that
+//! fell too far behind are told how many values they missed
+
+use core::array;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize};
+
+/// A fixed-capacity, single-producer, multi-consumer broadcast channel with `SUBS` subscribers
+pub struct Channel<T, const N: usize, const SUBS: usize> {
+    inner: Inner<[Slot<T>; N]>,
+    subscribers: [Cursor; SUBS],
+}
+
+impl<T, const N: usize, const SUBS: usize> Channel<T, N, SUBS> {
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+        }
+
+        Self {
+            inner: Inner {
+                write: Cursor::new(),
+                slots: [const { Slot::new() }; N],
+            },
+            subscribers: [const { Cursor::new() }; SUBS],
+        }
+    }
+
+    /// Splits this channel into its sender and exactly `SUBS` receivers
+    ///
+    /// Receivers only observe the values sent after the split. The channel is mutably borrowed
+    /// for as long as any part is live so the channel can be statically allocated, and split
+    /// once, or live on the stack
+    pub fn split(&mut self) -> (Sender<'_, T>, [Receiver<'_, T>; SUBS]) {
+        let write = *self.inner.write.position.get_mut();
+        for cursor in &mut self.subscribers {
+            *cursor.position.get_mut() = write;
+        }
+
+        let inner = &self.inner;
+        let subscribers = &self.subscribers;
+        let receivers = array::from_fn(|index| Receiver {
+            inner,
+            cursor: &subscribers[index],
+        });
+
+        (Sender { inner }, receivers)
+    }
+}
+
+impl<T, const N: usize, const SUBS: usize> Drop for Channel<T, N, SUBS> {
+    fn drop(&mut self) {
+        for slot in &mut self.inner.slots {
+            if *slot.position.get_mut() != EMPTY {
+                // SAFETY: a slot with a position holds an initialized value
+                unsafe {
+                    slot.value.get_mut().assume_init_drop();
+                }
+            }
+        }
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+}
+
+impl<T> Sender<'_, T> {
+    /// Sends data to all the receivers, overwriting the oldest value if the channel is full
+    ///
+    /// Returns an `Err` if a receiver is cloning the oldest value at this very moment; the
+    /// sender does not wait for it as the receiver may be the code it preempted
+    pub fn send(&self, value: T) -> Result<(), T> {
+        self.inner.send(value)
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+/// A receiver side of a channel
+pub struct Receiver<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+    cursor: &'a Cursor,
+}
+
+impl<T> Receiver<'_, T> {
+    /// Receives a clone of the oldest value this receiver has not seen yet
+    ///
+    /// Returns `Err(Lagged)` if values were overwritten before this receiver could see them; the
+    /// next call returns the oldest value that is still in the channel
+    pub fn recv(&self) -> Result<T, RecvError>
+    where
+        T: Clone,
+    {
+        self.inner.recv(self.cursor)
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+/// The error returned by `Receiver::recv`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// The receiver has seen every value sent so far
+    Empty,
+    /// The receiver fell behind and this many values were overwritten before it could see them
+    Lagged(usize),
+}
+
+struct Inner<S>
+where
+    S: ?Sized,
+{
+    write: Cursor,
+    slots: S,
+}
+
+/// Padded to a cache line so that the sender and the receivers do not write to the same cache
+/// line when they update their cursors
+///
+/// Positions wrap around at a multiple of the capacity so that a position always maps to the same
+/// slot, whatever the capacity
+#[repr(align(64))]
+struct Cursor {
+    position: AtomicUsize,
+}
+
+impl Cursor {
+    const fn new() -> Self {
+        Self {
+            position: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// The position of a slot that has never been written
+const EMPTY: usize = usize::MAX;
+
+/// The `lock` bit held by the sender while it writes a slot; receivers add `READER` to it while
+/// they clone the value
+const WRITING: usize = 1;
+const READER: usize = 2;
+
+struct Slot<T> {
+    lock: AtomicUsize,
+    /// The position of the value held by this slot
+    position: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            lock: AtomicUsize::new(0),
+            position: AtomicUsize::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Inner<[Slot<T>]> {
+    fn send(&self, value: T) -> Result<(), T> {
+        let current_write = self.write.position.load(atomic::Ordering::Relaxed);
+        let slot = &self.slots[current_write % self.slots.len()];
+
+        // Acquire: the receivers' clones of the previous value happen before we drop it
+        if slot
+            .lock
+            .compare_exchange(
+                0,
+                WRITING,
+                atomic::Ordering::Acquire,
+                atomic::Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return Err(value);
+        }
+
+        let value_ptr = slot.value.get().cast::<T>();
+        if slot.position.load(atomic::Ordering::Relaxed) != EMPTY {
+            // SAFETY: the slot holds a value and the lock grants exclusive access to it
+            unsafe {
+                value_ptr.drop_in_place();
+            }
+        }
+        // SAFETY: the lock grants exclusive access to the slot
+        unsafe {
+            value_ptr.write(value);
+        }
+        slot.position
+            .store(current_write, atomic::Ordering::Relaxed);
+
+        // Release: the writes above happen before a receiver clones the value
+        // receivers may have added themselves to `lock` in the meantime so we only clear our bit
+        slot.lock.fetch_and(!WRITING, atomic::Ordering::Release);
+
+        // Release: the slot writes above happen before the receivers observe the new `write`
+        self.write
+            .position
+            .store(self.next(current_write), atomic::Ordering::Release);
+
+        Ok(())
+    }
+
+    fn recv(&self, cursor: &Cursor) -> Result<T, RecvError>
+    where
+        T: Clone,
+    {
+        let capacity = self.slots.len();
+
+        loop {
+            let current_read = cursor.position.load(atomic::Ordering::Relaxed);
+            // Acquire: the slot writes that precede the `write` store in `send` are visible
+            let acquired_write = self.write.position.load(atomic::Ordering::Acquire);
+            let len = self.distance(current_read, acquired_write);
+
+            if len == 0 {
+                return Err(RecvError::Empty);
+            }
+
+            if len > capacity {
+                let oldest = self.distance(capacity, acquired_write);
+                cursor.position.store(oldest, atomic::Ordering::Relaxed);
+                return Err(RecvError::Lagged(len - capacity));
+            }
+
+            let slot = &self.slots[current_read % capacity];
+            // Acquire: the sender's write of the value happens before we clone it
+            if slot.lock.fetch_add(READER, atomic::Ordering::Acquire) & WRITING != 0 {
+                slot.lock.fetch_sub(READER, atomic::Ordering::Relaxed);
+
+                // the sender is overwriting this very value; we will not wait for it as it may
+                // be the code we preempted
+                cursor
+                    .position
+                    .store(self.next(current_read), atomic::Ordering::Relaxed);
+                return Err(RecvError::Lagged(1));
+            }
+
+            let value = if slot.position.load(atomic::Ordering::Relaxed) == current_read {
+                // SAFETY: the slot holds the value at `current_read` and the sender does not
+                // write it while we are registered as a reader
+                Some(unsafe { (*slot.value.get()).assume_init_ref().clone() })
+            } else {
+                // the value was overwritten after `write` was loaded
+                None
+            };
+
+            // Release: our clone happens before the sender drops the value
+            slot.lock.fetch_sub(READER, atomic::Ordering::Release);
+
+            if let Some(value) = value {
+                cursor
+                    .position
+                    .store(self.next(current_read), atomic::Ordering::Relaxed);
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Returns the position at which positions wrap around to `0`
+    fn wrap(&self) -> usize {
+        let capacity = self.slots.len();
+
+        capacity * (usize::MAX / 2 / capacity)
+    }
+
+    /// Returns the position that follows `position`
+    fn next(&self, position: usize) -> usize {
+        let position = position + 1;
+
+        if position == self.wrap() { 0 } else { position }
+    }
+
+    /// Returns the number of positions from `from` to `to`; `from` may also be a distance to
+    /// step back from `to`
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + self.wrap() - from) % self.wrap()
+    }
+}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread
+// where the receivers share them; therefore the value must be Send and Sync as well
+unsafe impl<T> Send for Sender<'_, T> where T: Send + Sync {}
+
+// SAFETY: allowing the handle to move to another thread, allows receiving values on another
+// thread and receivers on different threads concurrently clone the same value; therefore the
+// value must be Send and Sync as well
+unsafe impl<T> Send for Receiver<'_, T> where T: Send + Sync {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_receiver_sees_every_value() {
+        let mut channel = Channel::<i32, 4, 3>::new();
+        let (sender, receivers) = channel.split();
+
+        for receiver in &receivers {
+            assert_eq!(Err(RecvError::Empty), receiver.recv());
+        }
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Ok(()), sender.send(2));
+
+        for receiver in &receivers {
+            assert_eq!(Ok(1), receiver.recv());
+            assert_eq!(Ok(2), receiver.recv());
+            assert_eq!(Err(RecvError::Empty), receiver.recv());
+        }
+    }
+
+    #[test]
+    fn slow_receivers_lag() {
+        let mut channel = Channel::<i32, 3, 2>::new();
+        let (sender, [fast, slow]) = channel.split();
+
+        for value in 0..5 {
+            assert_eq!(Ok(()), sender.send(value));
+            assert_eq!(Ok(value), fast.recv());
+        }
+
+        assert_eq!(Err(RecvError::Lagged(2)), slow.recv());
+        for value in 2..5 {
+            assert_eq!(Ok(value), slow.recv());
+        }
+        assert_eq!(Err(RecvError::Empty), slow.recv());
+        assert_eq!(Err(RecvError::Empty), fast.recv());
+    }
+
+    #[test]
+    fn position_wrap_around() {
+        let mut channel = Channel::<i32, 3, 1>::new();
+        let inner: &Inner<[Slot<i32>]> = &channel.inner;
+        let wrap = inner.wrap();
+        *channel.inner.write.position.get_mut() = wrap - 4;
+
+        let (sender, [receiver]) = channel.split();
+        for value in 0..8 {
+            assert_eq!(Ok(()), sender.send(value));
+            assert_eq!(Ok(value), receiver.recv());
+        }
+        for value in 0..4 {
+            assert_eq!(Ok(()), sender.send(value));
+        }
+        assert_eq!(Err(RecvError::Lagged(1)), receiver.recv());
+        assert_eq!(Ok(1), receiver.recv());
+    }
+
+    #[test]
+    fn split_again_starts_at_the_write_cursor() {
+        let mut channel = Channel::<i32, 2, 1>::new();
+
+        let (sender, _) = channel.split();
+        assert_eq!(Ok(()), sender.send(1));
+
+        let (sender, [receiver]) = channel.split();
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+        assert_eq!(Ok(()), sender.send(2));
+        assert_eq!(Ok(2), receiver.recv());
+    }
+
+    #[test]
+    fn dropping_the_channel_drops_the_values() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut channel = Channel::<_, 2, 2>::new();
+        let (sender, [receiver, _]) = channel.split();
+
+        for _ in 0..3 {
+            assert!(sender.send(value.clone()).is_ok());
+        }
+        assert_eq!(3, Rc::strong_count(&value));
+        assert!(receiver.recv().is_err());
+        let received = receiver.recv();
+        assert!(received.is_ok());
+        assert_eq!(4, Rc::strong_count(&value));
+
+        drop(channel);
+        assert_eq!(2, Rc::strong_count(&value));
+        drop(received);
+    }
+
+    #[test]
+    fn concurrent_receivers() {
+        const MESSAGES: usize = 10_000;
+
+        let mut channel = Channel::<usize, 4, 2>::new();
+        let (sender, receivers) = channel.split();
+
+        std::thread::scope(|s| {
+            for receiver in receivers {
+                s.spawn(move || {
+                    let mut expected = 0;
+                    while expected < MESSAGES {
+                        match receiver.recv() {
+                            Ok(value) => {
+                                assert_eq!(expected, value);
+                                expected += 1;
+                            }
+                            Err(RecvError::Lagged(missed)) => expected += missed,
+                            Err(RecvError::Empty) => std::thread::yield_now(),
+                        }
+                    }
+                });
+            }
+
+            for value in 0..MESSAGES {
+                while sender.send(value).is_err() {}
+            }
+        });
+    }
+
+    #[test]
+    fn check_handles_are_send() {
+        is_send::<Sender<i32>>();
+        is_send::<Receiver<i32>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
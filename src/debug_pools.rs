@@ -0,0 +1,63 @@
+//! Runtime diagnostics for the memory pools
+//!
+//! Enabled by the `debug-pools` feature. Each pool slot is tagged as free or in use and tracks the
+//! pool that manages it. Returning a slot that is already free, or returning a slot to a pool that
+//! does not manage it, is reported to the installed hook and the operation is skipped so that the
+//! pool's free list is not corrupted
+//!
+//! Additionally, the memory of box and arc slots is overwritten with [`POISON`] after their
+//! contents have been destroyed
+
+use core::mem;
+use core::sync::atomic::{self, AtomicPtr};
+
+/// The byte pattern written over the contents of a slot after they have been destroyed
+pub const POISON: u8 = 0xA5;
+
+/// A misuse of a pool slot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// A slot that is already free was returned to its pool
+    DoubleFree {
+        /// Address of the slot
+        slot: usize,
+    },
+    /// A slot was returned to a pool that does not manage it
+    ForeignSlot {
+        /// Address of the slot
+        slot: usize,
+    },
+}
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Installs the function that gets called when a violation is detected
+///
+/// The default hook panics
+pub fn set_hook(hook: fn(Violation)) {
+    HOOK.store(hook as *mut (), atomic::Ordering::Release);
+}
+
+pub(crate) fn report(violation: Violation) {
+    let hook = HOOK.load(atomic::Ordering::Acquire);
+
+    if hook.is_null() {
+        panic!("pool violation: {violation:?}")
+    } else {
+        // SAFETY: only `set_hook` stores non-null values and those are `fn(Violation)` pointers
+        let hook = unsafe { mem::transmute::<*mut (), fn(Violation)>(hook) };
+        hook(violation)
+    }
+}
+
+/// # Safety
+/// - `ptr` must be valid for writes of `size_of::<T>()` bytes
+pub(crate) unsafe fn poison<T>(ptr: *mut T) {
+    // SAFETY: `ptr` is valid for writes as per the caller contract
+    unsafe { ptr.cast::<u8>().write_bytes(POISON, mem::size_of::<T>()) }
+}
+
+/// Serializes the tests that install a hook, as the hook is global
+#[cfg(test)]
+pub(crate) static TEST_HOOK: std::sync::Mutex<()> = std::sync::Mutex::new(());
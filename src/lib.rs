@@ -1,7 +1,7 @@
 //! FIxed KApacity stuff
 
 #![deny(missing_docs)]
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(clippy::missing_safety_doc)]
 #![deny(clippy::undocumented_unsafe_blocks)]
 
@@ -9,9 +9,24 @@
 pub mod arc_pool;
 #[cfg(target_arch = "arm")]
 pub mod box_pool;
+pub mod broadcast;
+#[cfg(all(target_arch = "arm", feature = "debug-pools"))]
+pub mod debug_pools;
+pub mod mailbox;
+pub mod mpmc;
+#[cfg(target_arch = "arm")]
+pub mod mpsc;
 #[cfg(target_arch = "arm")]
 pub mod object_pool;
+pub mod overwrite;
+pub mod pipe;
+pub mod select;
+pub mod seqlock;
 pub mod spsc;
 #[cfg(target_arch = "arm")]
+pub mod stack;
+#[cfg(target_arch = "arm")]
 mod treiber;
 pub mod vec;
+pub mod wait;
+mod waker;
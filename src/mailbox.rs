@@ -0,0 +1,219 @@
+//! A mailbox that holds the latest value sent through it, implemented as a triple buffer
+//!
+//! The writer and the reader each own one of the three buffers; the third one is the "back"
+//! buffer which holds the latest complete value. Both sides swap their buffer with the back buffer
+//! in a single atomic operation so the writer never waits on the reader and the reader never sees
+//! a value that is being written
+
+use core::cell::{Cell, UnsafeCell};
+use core::sync::atomic::{self, AtomicU8};
+
+/// The bits of `back` that hold the index of the back buffer
+const INDEX: u8 = 0b011;
+/// Set in `back` when the back buffer holds a value the reader has not seen yet
+const UPDATED: u8 = 0b100;
+
+/// A single-producer, single-consumer (SPSC) mailbox that holds the latest value sent through it
+pub struct Mailbox<T> {
+    buffers: [UnsafeCell<Option<T>>; 3],
+    back: AtomicU8,
+    /// The index of the writer's buffer; only accessed by the writer
+    write: Cell<u8>,
+    /// The index of the reader's buffer; only accessed by the reader
+    read: Cell<u8>,
+}
+
+impl<T> Mailbox<T> {
+    /// Creates a new, empty mailbox
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            buffers: [const { UnsafeCell::new(None) }; 3],
+            back: AtomicU8::new(2),
+            write: Cell::new(0),
+            read: Cell::new(1),
+        }
+    }
+
+    /// Splits this mailbox into writer and reader parts
+    ///
+    /// The mailbox is mutably borrowed for as long as either part is live. Splitting again keeps
+    /// the latest value
+    pub fn split(&mut self) -> (Writer<'_, T>, Reader<'_, T>) {
+        (Writer { mailbox: self }, Reader { mailbox: self })
+    }
+}
+
+/// The writer side of a mailbox
+pub struct Writer<'a, T> {
+    mailbox: &'a Mailbox<T>,
+}
+
+impl<T> Writer<'_, T> {
+    /// Replaces the value in the mailbox
+    ///
+    /// This never waits on the reader. Values that the reader skipped, or is done with, are
+    /// dropped by later writes
+    pub fn write(&mut self, value: T) {
+        let write = self.mailbox.write.get();
+
+        // SAFETY: the writer's buffer is only accessed by the writer; `&mut self` ensures there's
+        // no reference to it
+        unsafe {
+            *self.mailbox.buffers[usize::from(write)].get() = Some(value);
+        }
+
+        // AcqRel: our buffer write above happens before the reader reads it and the reader's
+        // reads of the buffer we get back happen before we write to it
+        let back = self
+            .mailbox
+            .back
+            .swap(write | UPDATED, atomic::Ordering::AcqRel);
+        self.mailbox.write.set(back & INDEX);
+    }
+}
+
+/// The reader side of a mailbox
+pub struct Reader<'a, T> {
+    mailbox: &'a Mailbox<T>,
+}
+
+impl<T> Reader<'_, T> {
+    /// Returns the latest value written to the mailbox
+    ///
+    /// Returns `None` if nothing has been written yet
+    pub fn read(&mut self) -> Option<&T> {
+        if self.has_update() {
+            // AcqRel: the writer's write of the back buffer happens before we read it and our
+            // reads of the buffer we give back happen before the writer writes to it
+            let back = self
+                .mailbox
+                .back
+                .swap(self.mailbox.read.get(), atomic::Ordering::AcqRel);
+            self.mailbox.read.set(back & INDEX);
+        }
+
+        // SAFETY: the reader's buffer is only accessed by the reader; the returned reference
+        // borrows `self` so the buffer cannot be given back while it's live
+        unsafe { (*self.mailbox.buffers[usize::from(self.mailbox.read.get())].get()).as_ref() }
+    }
+
+    /// Returns `true` if a value was written since the last `read`
+    pub fn has_update(&self) -> bool {
+        self.mailbox.back.load(atomic::Ordering::Relaxed) & UPDATED != 0
+    }
+}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Writer<'_, T> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Reader<'_, T> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_latest_value() {
+        let mut mailbox = Mailbox::new();
+        let (mut writer, mut reader) = mailbox.split();
+
+        assert!(!reader.has_update());
+        assert_eq!(None, reader.read());
+
+        writer.write(1);
+        assert!(reader.has_update());
+        assert_eq!(Some(&1), reader.read());
+        assert!(!reader.has_update());
+        assert_eq!(Some(&1), reader.read());
+
+        for value in 2..10 {
+            writer.write(value);
+        }
+        assert_eq!(Some(&9), reader.read());
+        assert_eq!(Some(&9), reader.read());
+    }
+
+    #[test]
+    fn split_again_keeps_the_latest_value() {
+        let mut mailbox = Mailbox::new();
+
+        {
+            let (mut writer, mut reader) = mailbox.split();
+            writer.write(1);
+            assert_eq!(Some(&1), reader.read());
+            writer.write(2);
+        }
+
+        let (_writer, mut reader) = mailbox.split();
+        assert_eq!(Some(&2), reader.read());
+    }
+
+    #[test]
+    fn drops_values() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut mailbox = Mailbox::new();
+        let (mut writer, mut reader) = mailbox.split();
+
+        for _ in 0..10 {
+            writer.write(value.clone());
+        }
+        // the back buffer and the writer's buffer
+        assert_eq!(3, Rc::strong_count(&value));
+
+        assert!(reader.read().is_some());
+        writer.write(value.clone());
+        // the reader's buffer and the back buffer
+        assert_eq!(3, Rc::strong_count(&value));
+
+        drop(mailbox);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn no_tearing() {
+        const WRITES: usize = 100_000;
+
+        let mut mailbox = Mailbox::new();
+        let (mut writer, mut reader) = mailbox.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for value in 1..=WRITES {
+                    writer.write([value; 8]);
+                }
+            });
+
+            let mut latest = 0;
+            while latest != WRITES {
+                if let Some(values) = reader.read() {
+                    assert!(values.iter().all(|value| *value == values[0]));
+                    assert!(values[0] >= latest);
+                    latest = values[0];
+                }
+                std::thread::yield_now();
+            }
+        });
+    }
+
+    #[test]
+    fn check_writer_is_send() {
+        is_send::<Writer<i32>>();
+    }
+
+    #[test]
+    fn check_reader_is_send() {
+        is_send::<Reader<i32>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
@@ -0,0 +1,426 @@
+//! A fixed-capacity, multi-producer, multi-consumer (MPMC) channel
+//!
+//! This is Dmitry Vyukov's bounded queue: each slot carries a stamp that tells producers and
+//! consumers whether the slot is ready to be written or read on the current lap around the
+//! buffer. A stamp packs the lap in its upper bits and the slot index in its lower bits so the
+//! capacity need not be a power of two
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize};
+
+/// A fixed-capacity, multi-producer, multi-consumer (MPMC) channel
+pub struct Channel<T, const N: usize> {
+    inner: Inner<[Slot<T>; N]>,
+}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+        }
+
+        let mut slots = [const { Slot::new(0) }; N];
+        let mut index = 0;
+        while index < N {
+            slots[index] = Slot::new(index);
+            index += 1;
+        }
+
+        Self {
+            inner: Inner {
+                head: Cursor::new(),
+                tail: Cursor::new(),
+                one_lap: (N + 1).next_power_of_two(),
+                slots,
+            },
+        }
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// Both parts can be cloned to get more producers and consumers. Only a shared borrow is
+    /// needed so the channel can be placed in a `static` and split from several places
+    pub fn split(&self) -> (Sender<'_, T>, Receiver<'_, T>) {
+        let inner = &self.inner;
+
+        (Sender { inner }, Receiver { inner })
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        let inner: &mut Inner<[Slot<T>]> = &mut self.inner;
+        inner.drop_items();
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+}
+
+impl<T> Sender<'_, T> {
+    /// Sends data through the channel
+    ///
+    /// Returns an `Err` if the channel is observed as being full
+    pub fn send(&self, value: T) -> Result<(), T> {
+        self.inner.send(value)
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+impl<T> Clone for Sender<'_, T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner }
+    }
+}
+
+/// The receiver side of a channel
+pub struct Receiver<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+}
+
+impl<T> Receiver<'_, T> {
+    /// Receives data from the channel
+    ///
+    /// Returns `None` if the channel is observed as being empty
+    pub fn recv(&self) -> Option<T> {
+        self.inner.recv()
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+impl<T> Clone for Receiver<'_, T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner }
+    }
+}
+
+struct Inner<S>
+where
+    S: ?Sized,
+{
+    /// The position of the next slot to read
+    head: Cursor,
+    /// The position of the next slot to write
+    tail: Cursor,
+    /// The stamp increment of a full lap around the buffer
+    one_lap: usize,
+    slots: S,
+}
+
+/// Padded to a cache line so that producers and consumers do not write to the same cache line
+/// when they update their cursors
+#[repr(align(64))]
+struct Cursor {
+    position: AtomicUsize,
+}
+
+impl Cursor {
+    const fn new() -> Self {
+        Self {
+            position: AtomicUsize::new(0),
+        }
+    }
+}
+
+struct Slot<T> {
+    /// `position` if the slot is ready to be written; `position + 1` if it's ready to be read
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    const fn new(stamp: usize) -> Self {
+        Self {
+            stamp: AtomicUsize::new(stamp),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Inner<[Slot<T>]> {
+    fn send(&self, value: T) -> Result<(), T> {
+        let mut tail = self.tail.position.load(atomic::Ordering::Relaxed);
+
+        loop {
+            let slot = self.slot(tail);
+            // Acquire: the consumer's read of the previous value happens before we overwrite it
+            let stamp = slot.stamp.load(atomic::Ordering::Acquire);
+
+            if stamp == tail {
+                // the stamp, not the cursor, publishes the value so claiming the slot needs no
+                // ordering
+                match self.tail.position.compare_exchange_weak(
+                    tail,
+                    self.next(tail),
+                    atomic::Ordering::Relaxed,
+                    atomic::Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: winning the CAS grants exclusive access to the slot
+                        unsafe {
+                            slot.value.get().cast::<T>().write(value);
+                        }
+
+                        // Release: the value write above happens before a consumer reads it
+                        slot.stamp.store(tail + 1, atomic::Ordering::Release);
+                        return Ok(());
+                    }
+
+                    Err(current) => tail = current,
+                }
+            } else if stamp.wrapping_add(self.one_lap) == tail + 1 {
+                // the slot still holds the value from the previous lap, or a consumer is
+                // reading it; we report the channel as full rather than wait for a consumer
+                // which may have been preempted by us
+                return Err(value);
+            } else {
+                // another producer claimed the slot
+                tail = self.tail.position.load(atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn recv(&self) -> Option<T> {
+        let mut head = self.head.position.load(atomic::Ordering::Relaxed);
+
+        loop {
+            let slot = self.slot(head);
+            // Acquire: the producer's write of the value happens before we read it
+            let stamp = slot.stamp.load(atomic::Ordering::Acquire);
+
+            if stamp == head + 1 {
+                match self.head.position.compare_exchange_weak(
+                    head,
+                    self.next(head),
+                    atomic::Ordering::Relaxed,
+                    atomic::Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: winning the CAS grants exclusive access to the initialized slot
+                        let value = unsafe { slot.value.get().cast::<T>().read() };
+
+                        // Release: the value read above happens before a producer overwrites it
+                        slot.stamp
+                            .store(head.wrapping_add(self.one_lap), atomic::Ordering::Release);
+                        return Some(value);
+                    }
+
+                    Err(current) => head = current,
+                }
+            } else if stamp == head {
+                // nothing was written to the slot on this lap, or a producer is writing it
+                return None;
+            } else {
+                // another consumer claimed the slot
+                head = self.head.position.load(atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn slot(&self, position: usize) -> &Slot<T> {
+        &self.slots[position & (self.one_lap - 1)]
+    }
+
+    /// Returns the position that follows `position`
+    fn next(&self, position: usize) -> usize {
+        let index = position & (self.one_lap - 1);
+
+        if index + 1 < self.slots.len() {
+            position + 1
+        } else {
+            (position & !(self.one_lap - 1)).wrapping_add(self.one_lap)
+        }
+    }
+
+    fn drop_items(&mut self) {
+        let tail = *self.tail.position.get_mut();
+        let mut head = *self.head.position.get_mut();
+
+        while head != tail {
+            // SAFETY: slots between the `head` and `tail` cursors are initialized
+            unsafe {
+                self.slot(head).value.get().cast::<T>().drop_in_place();
+            }
+            head = self.next(head);
+        }
+
+        *self.head.position.get_mut() = head;
+    }
+}
+
+// SAFETY: the channel hands out its values to whichever thread receives them and the slots are
+// guarded by their stamps; therefore the value only needs to be Send
+unsafe impl<T, const N: usize> Sync for Channel<T, N> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Sender<'_, T> where T: Send {}
+
+// SAFETY: the handle can be cloned so sharing it is equivalent to sending a clone
+unsafe impl<T> Sync for Sender<'_, T> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Receiver<'_, T> where T: Send {}
+
+// SAFETY: the handle can be cloned so sharing it is equivalent to sending a clone
+unsafe impl<T> Sync for Receiver<'_, T> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_order() {
+        let channel = Channel::<i32, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        assert_eq!(None, receiver.recv());
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Ok(()), sender.send(2));
+        assert_eq!(Err(3), sender.send(3));
+        assert_eq!(Some(1), receiver.recv());
+        assert_eq!(Some(2), receiver.recv());
+        assert_eq!(None, receiver.recv());
+    }
+
+    #[test]
+    fn works_with_non_power_of_two() {
+        let channel = Channel::<i32, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        for lap in 0..4 {
+            for value in 0..3 {
+                assert_eq!(Ok(()), sender.send(lap * 3 + value));
+            }
+            assert!(sender.send(-1).is_err());
+
+            for value in 0..3 {
+                assert_eq!(Some(lap * 3 + value), receiver.recv());
+            }
+            assert_eq!(None, receiver.recv());
+        }
+    }
+
+    #[test]
+    fn position_wrap_around() {
+        let mut channel = Channel::<i32, 3>::new();
+        let one_lap = channel.inner.one_lap;
+        // start on the last lap before the positions overflow
+        let lap = usize::MAX & !(one_lap - 1);
+        *channel.inner.head.position.get_mut() = lap;
+        *channel.inner.tail.position.get_mut() = lap;
+        for (index, slot) in channel.inner.slots.iter_mut().enumerate() {
+            *slot.stamp.get_mut() = lap + index;
+        }
+
+        let (sender, receiver) = channel.split();
+        for value in 0..9 {
+            assert_eq!(Ok(()), sender.send(value));
+            assert_eq!(Ok(()), sender.send(value + 100));
+            assert_eq!(Some(value), receiver.recv());
+            assert_eq!(Some(value + 100), receiver.recv());
+        }
+    }
+
+    #[test]
+    fn static_placement() {
+        static CHANNEL: Channel<i32, 4> = Channel::new();
+
+        let (sender, _) = CHANNEL.split();
+        let (_, receiver) = CHANNEL.split();
+
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Some(1), receiver.recv());
+    }
+
+    #[test]
+    fn many_producers_and_consumers() {
+        const PRODUCERS: usize = 3;
+        const CONSUMERS: usize = 3;
+        const MESSAGES: usize = 10_000;
+
+        let channel = Channel::<usize, 5>::new();
+        let (sender, receiver) = channel.split();
+        let received = AtomicUsize::new(0);
+        let sum = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for producer in 0..PRODUCERS {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for value in 0..MESSAGES {
+                        let mut value = producer * MESSAGES + value;
+                        while let Err(v) = sender.send(value) {
+                            value = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+
+            for _ in 0..CONSUMERS {
+                let receiver = receiver.clone();
+                let (received, sum) = (&received, &sum);
+                s.spawn(move || {
+                    while received.load(atomic::Ordering::Relaxed) < PRODUCERS * MESSAGES {
+                        if let Some(value) = receiver.recv() {
+                            sum.fetch_add(value, atomic::Ordering::Relaxed);
+                            received.fetch_add(1, atomic::Ordering::Relaxed);
+                        } else {
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+        });
+
+        let total = PRODUCERS * MESSAGES;
+        assert_eq!(total * (total - 1) / 2, sum.into_inner());
+    }
+
+    #[test]
+    fn dropping_the_channel_drops_pending_items() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let channel = Channel::<_, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        for _ in 0..3 {
+            assert!(sender.send(value.clone()).is_ok());
+        }
+        assert!(receiver.recv().is_some());
+        assert!(sender.send(value.clone()).is_ok());
+        assert_eq!(4, Rc::strong_count(&value));
+
+        drop(channel);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn check_handles_are_send_and_sync() {
+        is_send_and_sync::<Sender<i32>>();
+        is_send_and_sync::<Receiver<i32>>();
+        is_send_and_sync::<Channel<i32, 1>>();
+    }
+
+    fn is_send_and_sync<T>()
+    where
+        T: Send + Sync,
+    {
+    }
+}
@@ -0,0 +1,360 @@
+//! A fixed-capacity, multi-producer, single-consumer (MPSC) channel built on top of LL/SC
+//! instructions
+//!
+//! Producers reserve a slot by advancing the write cursor with a LL/SC pair, which an interrupt
+//! handler can do without ever waiting on the code it preempted, and then publish the value by
+//! flagging the slot as ready. The receiver only reads the slot at its cursor so it's wait-free
+//!
+//! Currently only ARM is supported
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::sync::atomic::{self, AtomicBool, AtomicUsize};
+
+use crate::treiber;
+
+/// A fixed-capacity, multi-producer, single-consumer (MPSC) channel
+pub struct Channel<T, const N: usize> {
+    inner: Inner<[Slot<T>; N]>,
+}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+            assert!(N <= usize::MAX / 4, "capacity is too large");
+        }
+
+        Self {
+            inner: Inner {
+                read: Cursor::new(),
+                write: Cursor::new(),
+                slots: [const { Slot::new() }; N],
+            },
+        }
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// The sender can be cloned to get more producers. The channel is mutably borrowed for as
+    /// long as either part is live so there's a single receiver
+    pub fn split(&mut self) -> (Sender<'_, T>, Receiver<'_, T>) {
+        let inner = &self.inner;
+
+        (Sender { inner }, Receiver { inner })
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        for slot in &mut self.inner.slots {
+            if *slot.ready.get_mut() {
+                // SAFETY: a ready slot holds an initialized value
+                unsafe {
+                    slot.value.get_mut().assume_init_drop();
+                }
+            }
+        }
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+}
+
+impl<T> Sender<'_, T> {
+    /// Sends data through the channel
+    ///
+    /// Returns an `Err` if the channel is observed as being full
+    pub fn send(&self, value: T) -> Result<(), T> {
+        self.inner.send(value)
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+impl<T> Clone for Sender<'_, T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner }
+    }
+}
+
+/// The receiver side of a channel
+pub struct Receiver<'a, T> {
+    inner: &'a Inner<[Slot<T>]>,
+}
+
+impl<T> Receiver<'_, T> {
+    /// Receives data from the channel
+    ///
+    /// Returns `None` if the channel is observed as being empty. Values are received in the
+    /// order their slots were reserved so a producer that was preempted between reserving a slot
+    /// and publishing its value holds back the values sent after it
+    pub fn recv(&self) -> Option<T> {
+        // SAFETY: `split` API ensures there's a single receiver
+        unsafe { self.inner.recv() }
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.slots.len()
+    }
+}
+
+struct Inner<S>
+where
+    S: ?Sized,
+{
+    read: Cursor,
+    write: Cursor,
+    slots: S,
+}
+
+/// Padded to a cache line so that producers and the consumer do not write to the same cache line
+/// when they update their cursors
+///
+/// Positions run from `0` to `2 * N` (exclusive) so that a full channel can be told apart from
+/// an empty one and so that wrapping around is correct for any `N`
+#[repr(align(64))]
+struct Cursor {
+    position: AtomicUsize,
+}
+
+impl Cursor {
+    const fn new() -> Self {
+        Self {
+            position: AtomicUsize::new(0),
+        }
+    }
+}
+
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Inner<[Slot<T>]> {
+    fn send(&self, value: T) -> Result<(), T> {
+        let capacity = self.slots.len();
+        let write_addr = NonNull::from(&self.write.position).cast::<usize>();
+
+        let reserved = loop {
+            // SAFETY: `write_addr` is a valid pointer
+            let current_write = unsafe { treiber::load_link(write_addr) };
+            // the read cursor is loaded within the LL/SC pair so that it's not older than
+            // `current_write`; otherwise the distance between the two could be off by laps
+            // Acquire: the receiver's read of a slot happens before we overwrite it
+            let acquired_read = self.read.position.load(atomic::Ordering::Acquire);
+
+            if self.distance(acquired_read, current_write) == capacity {
+                treiber::clear_load_link();
+
+                return Err(value);
+            }
+
+            // SAFETY: `write_addr` is a valid pointer
+            if unsafe { treiber::store_conditional(write_addr, self.next(current_write)).is_ok() } {
+                break current_write;
+            }
+        };
+
+        let slot = &self.slots[reserved % capacity];
+        // SAFETY: the reservation grants exclusive access to the slot
+        unsafe {
+            slot.value.get().cast::<T>().write(value);
+        }
+        // Release: the value write above happens before the receiver reads it
+        slot.ready.store(true, atomic::Ordering::Release);
+
+        Ok(())
+    }
+
+    /// # Safety
+    /// - Caller must ensure that there's a single receiver
+    unsafe fn recv(&self) -> Option<T> {
+        let current_read = self.read.position.load(atomic::Ordering::Relaxed);
+        let slot = &self.slots[current_read % self.slots.len()];
+
+        // Acquire: the producer's write of the value happens before we read it
+        if !slot.ready.load(atomic::Ordering::Acquire) {
+            return None;
+        }
+
+        // SAFETY: a ready slot holds an initialized value which only the receiver accesses
+        let value = unsafe { slot.value.get().cast::<T>().read() };
+        slot.ready.store(false, atomic::Ordering::Relaxed);
+
+        // Release: the value read and the `ready` store above happen before a producer reuses
+        // the slot
+        self.read
+            .position
+            .store(self.next(current_read), atomic::Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Returns the position that follows `position`
+    fn next(&self, position: usize) -> usize {
+        let position = position + 1;
+
+        if position == 2 * self.slots.len() {
+            0
+        } else {
+            position
+        }
+    }
+
+    /// Returns the number of positions from `read` to `write`
+    fn distance(&self, read: usize, write: usize) -> usize {
+        if write >= read {
+            write - read
+        } else {
+            2 * self.slots.len() - (read - write)
+        }
+    }
+}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Sender<'_, T> where T: Send {}
+
+// SAFETY: the handle can be cloned so sharing it is equivalent to sending a clone
+unsafe impl<T> Sync for Sender<'_, T> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Receiver<'_, T> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_order() {
+        let mut channel = Channel::<i32, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        assert_eq!(None, receiver.recv());
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Ok(()), sender.send(2));
+        assert_eq!(Err(3), sender.send(3));
+        assert_eq!(Some(1), receiver.recv());
+        assert_eq!(Some(2), receiver.recv());
+        assert_eq!(None, receiver.recv());
+    }
+
+    #[test]
+    fn works_with_non_power_of_two() {
+        let mut channel = Channel::<i32, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        for lap in 0..4 {
+            for value in 0..3 {
+                assert_eq!(Ok(()), sender.send(lap * 3 + value));
+            }
+            assert!(sender.send(-1).is_err());
+
+            for value in 0..3 {
+                assert_eq!(Some(lap * 3 + value), receiver.recv());
+            }
+            assert_eq!(None, receiver.recv());
+        }
+    }
+
+    #[test]
+    fn many_producers() {
+        const PRODUCERS: usize = 3;
+        const MESSAGES: usize = 10_000;
+
+        let mut channel = Channel::<usize, 5>::new();
+        let (sender, receiver) = channel.split();
+
+        std::thread::scope(|s| {
+            for producer in 0..PRODUCERS {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for value in 0..MESSAGES {
+                        let mut value = producer * MESSAGES + value;
+                        while let Err(v) = sender.send(value) {
+                            value = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+
+            // values from each producer arrive in order
+            let mut expected = [0; PRODUCERS];
+            for _ in 0..PRODUCERS * MESSAGES {
+                let value = loop {
+                    if let Some(value) = receiver.recv() {
+                        break value;
+                    }
+                    std::thread::yield_now();
+                };
+
+                let producer = value / MESSAGES;
+                assert_eq!(expected[producer], value % MESSAGES);
+                expected[producer] += 1;
+            }
+        });
+    }
+
+    #[test]
+    fn dropping_the_channel_drops_pending_items() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut channel = Channel::<_, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        for _ in 0..3 {
+            assert!(sender.send(value.clone()).is_ok());
+        }
+        assert!(receiver.recv().is_some());
+        assert!(sender.send(value.clone()).is_ok());
+        assert_eq!(4, Rc::strong_count(&value));
+
+        drop(channel);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn check_sender_is_send_and_sync() {
+        is_send::<Sender<i32>>();
+        is_sync::<Sender<i32>>();
+    }
+
+    #[test]
+    fn check_receiver_is_send() {
+        is_send::<Receiver<i32>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+
+    fn is_sync<T>()
+    where
+        T: Sync,
+    {
+    }
+}
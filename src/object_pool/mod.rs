@@ -2,10 +2,13 @@
 //!
 //! The objects managed by a pool are never destroyed, i.e. their destructor never runs
 
-use core::ops;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+use core::{borrow, cmp, fmt, hash, ops, ptr};
 
 use crate::treiber;
 use crate::treiber::{OwningNodePtr, Stack};
+use crate::vec::Storage;
 
 /// An object pool
 pub struct ObjectPool<T>
@@ -75,6 +78,170 @@ where
     inner: OwningNodePtr<Inner<T>>,
 }
 
+impl<T> Object<T> {
+    /// Projects the object into one of its parts, e.g. a field or a sub-slice
+    ///
+    /// The returned handle still owns the whole object and returns it to the pool when dropped
+    ///
+    /// This is an associated function so it does not shadow methods of `T`; use it as
+    /// `Object::map(object, ..)`
+    pub fn map<U, F>(mut this: Self, f: F) -> MappedObject<T, U>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let projection = NonNull::from(f(&mut this));
+
+        MappedObject {
+            _object: this,
+            projection,
+        }
+    }
+
+    /// Fallible version of `Object::map`
+    ///
+    /// Returns the original object if the closure returns `None`
+    pub fn filter_map<U, F>(mut this: Self, f: F) -> Result<MappedObject<T, U>, Self>
+    where
+        U: ?Sized,
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        if let Some(projection) = f(&mut this).map(NonNull::from) {
+            Ok(MappedObject {
+                _object: this,
+                projection,
+            })
+        } else {
+            Err(this)
+        }
+    }
+}
+
+impl<T> fmt::Debug for Object<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for Object<T>
+where
+    T: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        T::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T> ufmt::uDebug for Object<T>
+where
+    T: ufmt::uDebug,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        T::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Object<T>
+where
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        T::serialize(self, serializer)
+    }
+}
+
+impl<T> PartialEq for Object<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        T::eq(self, other)
+    }
+}
+
+impl<T> Eq for Object<T> where T: Eq {}
+
+impl<T> PartialOrd for Object<T>
+where
+    T: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        T::partial_cmp(self, other)
+    }
+}
+
+impl<T> Ord for Object<T>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        T::cmp(self, other)
+    }
+}
+
+impl<T> hash::Hash for Object<T>
+where
+    T: hash::Hash,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: hash::Hasher,
+    {
+        T::hash(self, state)
+    }
+}
+
+impl<T> fmt::Display for Object<T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        T::fmt(self, f)
+    }
+}
+
+impl<T> fmt::Pointer for Object<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&ptr::from_ref::<T>(self), f)
+    }
+}
+
+impl<T> borrow::Borrow<T> for Object<T> {
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T> borrow::BorrowMut<T> for Object<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
+impl<T> AsRef<T> for Object<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> AsMut<T> for Object<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self
+    }
+}
+
 impl<T> ops::Deref for Object<T> {
     type Target = T;
 
@@ -114,6 +281,127 @@ impl<const N: usize> AsMut<[u8]> for Object<[u8; N]> {
     }
 }
 
+// SAFETY: the object is never deallocated nor moved so moving the handle does not move `S`
+unsafe impl<S> Storage for Object<S>
+where
+    S: Storage,
+{
+    const INLINE: bool = false;
+
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        S::as_uninit_bytes(self)
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        S::as_uninit_bytes_mut(self)
+    }
+}
+
+/// An object projected into one of its parts
+///
+/// Created with `Object::map` or `Object::filter_map`. Dereferences to the projected part `U` but
+/// owns the whole `T`, which is returned to the pool when this handle goes out of scope
+pub struct MappedObject<T, U>
+where
+    T: 'static,
+    U: ?Sized,
+{
+    // only held to return the object to the pool on drop
+    _object: Object<T>,
+    projection: NonNull<U>,
+}
+
+impl<T, U> fmt::Debug for MappedObject<T, U>
+where
+    U: fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        U::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T, U> defmt::Format for MappedObject<T, U>
+where
+    U: defmt::Format + ?Sized,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        U::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T, U> ufmt::uDebug for MappedObject<T, U>
+where
+    U: ufmt::uDebug + ?Sized,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        U::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, U> serde::Serialize for MappedObject<T, U>
+where
+    U: serde::Serialize + ?Sized,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        U::serialize(self, serializer)
+    }
+}
+
+impl<T, U> ops::Deref for MappedObject<T, U>
+where
+    U: ?Sized,
+{
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: `projection` points into the object, which is never deallocated nor moved while
+        // `_object` is live. `_object` is not dereferenced while the projection exists
+        unsafe { self.projection.as_ref() }
+    }
+}
+
+impl<T, U> ops::DerefMut for MappedObject<T, U>
+where
+    U: ?Sized,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: see `Deref` implementation. `projection` was derived from a unique borrow
+        unsafe { self.projection.as_mut() }
+    }
+}
+
+// SAFETY: moving an object transfers ownership, like moving a box, so if the contents are Send then
+// the Object is also Send
+unsafe impl<T> Send for Object<T> where T: Send {}
+
+// SAFETY: the object does not add synchronization of its own
+unsafe impl<T> Sync for Object<T> where T: Sync {}
+
+// SAFETY: the handle owns the whole object and gives unique access to the projected part
+unsafe impl<T, U> Send for MappedObject<T, U>
+where
+    T: Send,
+    U: Send + ?Sized,
+{
+}
+
+// SAFETY: the handle only hands out shared references to the projected part from `&self`
+unsafe impl<T, U> Sync for MappedObject<T, U>
+where
+    T: Sync,
+    U: Sync + ?Sized,
+{
+}
+
 #[cfg(test)]
 mod tests {
     use core::sync::atomic::{self, AtomicBool};
@@ -154,6 +442,43 @@ mod tests {
         assert_eq!(value + 1, *same_object);
     }
 
+    #[test]
+    fn map_projects_and_returns_object_on_drop() {
+        static POOL: ObjectPool<[u8; 4]> = ObjectPool::new();
+
+        let unmanaged = Box::leak(Box::new(Unmanaged::new([1, 2, 3, 4])));
+        POOL.manage(unmanaged);
+
+        let object = POOL.request().unwrap();
+        let mut tail = Object::map(object, |buffer| &mut buffer[2..]);
+        assert_eq!([3, 4], *tail);
+
+        tail[0] = 0;
+
+        assert!(POOL.request().is_none(), "expected pool to be exhausted");
+
+        // returns the whole object to the pool
+        drop(tail);
+
+        let object = POOL.request().unwrap();
+        assert_eq!([1, 2, 0, 4], *object);
+    }
+
+    #[test]
+    fn filter_map_returns_original_object() {
+        static POOL: ObjectPool<Option<i32>> = ObjectPool::new();
+
+        let unmanaged = Box::leak(Box::new(Unmanaged::new(None)));
+        POOL.manage(unmanaged);
+
+        let object = POOL.request().unwrap();
+        let mut object = Object::filter_map(object, Option::as_mut).err().unwrap();
+        *object = Some(42);
+
+        let value = Object::filter_map(object, Option::as_mut).ok().unwrap();
+        assert_eq!(42, *value);
+    }
+
     #[test]
     fn if_managed_destructor_does_not_run() {
         struct Bomb;
@@ -195,4 +520,28 @@ mod tests {
         // did we destroy Evil?
         assert!(DESTROYED.load(atomic::Ordering::Relaxed));
     }
+
+    #[test]
+    fn check_object_is_send_and_sync() {
+        is_send::<Object<i32>>();
+        is_sync::<Object<i32>>();
+    }
+
+    #[test]
+    fn check_mapped_object_is_send_and_sync() {
+        is_send::<MappedObject<[i32; 2], [i32]>>();
+        is_sync::<MappedObject<[i32; 2], [i32]>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+
+    fn is_sync<T>()
+    where
+        T: Sync,
+    {
+    }
 }
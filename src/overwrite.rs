@@ -0,0 +1,412 @@
+//! A fixed-capacity, single-producer, single-consumer (SPSC) channel that overwrites its oldest
+//! element when full
+//!
+//! Elements live in `N + 2` buffers: one per slot of the ring, one owned by the sender and one
+//! owned by the receiver. The ring slots hold buffer *indices* which are exchanged with atomic
+//! operations so the sender never waits for the receiver, not even when the receiver is preempted
+//! while it moves an element out of its buffer
+
+use core::cell::{Cell, UnsafeCell};
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize};
+
+/// A fixed-capacity, single-producer, single-consumer (SPSC) channel that overwrites its oldest
+/// element when full
+pub struct Channel<T, const N: usize> {
+    write: AtomicUsize,
+    // receiver state
+    read: Cell<usize>,
+    held: Cell<usize>,
+    // sender state
+    spare: Cell<usize>,
+    slots: [AtomicUsize; N],
+    buffers: [UnsafeCell<MaybeUninit<T>>; N],
+    extra_buffers: [UnsafeCell<MaybeUninit<T>>; 2],
+}
+
+// a slot packs the position of the element it holds (`seq`), whether it holds an element that has
+// not been received (`FULL`) and the index of the buffer that holds the element, from most to
+// least significant bits
+impl<T, const N: usize> Channel<T, N> {
+    const IDX_BITS: u32 = usize::BITS - (N + 1).leading_zeros();
+    const FULL: usize = 1 << Self::IDX_BITS;
+    const IDX_MASK: usize = Self::FULL - 1;
+    const SEQ_SHIFT: u32 = Self::IDX_BITS + 1;
+    /// The cursor value that wraps around to `0` when `N` is not a power of two
+    const WRAP: usize = N * (usize::MAX / 2 / N);
+
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+            assert!(Self::SEQ_SHIFT + 16 <= usize::BITS, "capacity is too large");
+        }
+
+        let mut slots = [const { AtomicUsize::new(0) }; N];
+        let mut idx = 0;
+        while idx < N {
+            slots[idx] = AtomicUsize::new(idx);
+            idx += 1;
+        }
+
+        Self {
+            write: AtomicUsize::new(0),
+            read: Cell::new(0),
+            held: Cell::new(N + 1),
+            spare: Cell::new(N),
+            slots,
+            buffers: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            extra_buffers: [const { UnsafeCell::new(MaybeUninit::uninit()) }; 2],
+        }
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// The channel is mutably borrowed for as long as either part is live
+    pub fn split(&mut self) -> (Sender<'_, T, N>, Receiver<'_, T, N>) {
+        let inner = &*self;
+
+        (Sender { inner }, Receiver { inner })
+    }
+
+    fn pack(seq: usize, full: bool, idx: usize) -> usize {
+        (seq << Self::SEQ_SHIFT) | if full { Self::FULL } else { 0 } | idx
+    }
+
+    fn holds(slot: usize, seq: usize) -> bool {
+        slot & Self::FULL != 0 && (slot ^ (seq << Self::SEQ_SHIFT)) >> Self::SEQ_SHIFT == 0
+    }
+
+    /// Returns the cursor that is `len` positions after `cursor`
+    ///
+    /// Cursors wrap around at a multiple of `N` so that a cursor always refers to the same slot;
+    /// with a power of two `N` that's simply the whole range of `usize`
+    fn advance(cursor: usize, len: usize) -> usize {
+        if N.is_power_of_two() {
+            cursor.wrapping_add(len)
+        } else {
+            // cannot overflow as cursors stay below `WRAP` which is at most `usize::MAX / 2`
+            let cursor = cursor + len;
+
+            if cursor >= Self::WRAP {
+                cursor - Self::WRAP
+            } else {
+                cursor
+            }
+        }
+    }
+
+    /// Returns the cursor that is `len` positions before `cursor`
+    fn retreat(cursor: usize, len: usize) -> usize {
+        if N.is_power_of_two() {
+            cursor.wrapping_sub(len)
+        } else if cursor >= len {
+            cursor - len
+        } else {
+            cursor + Self::WRAP - len
+        }
+    }
+
+    /// Returns the number of positions from cursor `from` to cursor `to`
+    fn distance(from: usize, to: usize) -> usize {
+        if N.is_power_of_two() {
+            to.wrapping_sub(from)
+        } else if to >= from {
+            to - from
+        } else {
+            to + Self::WRAP - from
+        }
+    }
+
+    fn buffer(&self, idx: usize) -> *mut T {
+        let buffer = if idx < N {
+            &self.buffers[idx]
+        } else {
+            &self.extra_buffers[idx - N]
+        };
+
+        buffer.get().cast()
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        for slot in &self.slots {
+            let slot = slot.load(atomic::Ordering::Relaxed);
+
+            if slot & Self::FULL != 0 {
+                // SAFETY: a full slot refers to an initialized buffer
+                unsafe {
+                    self.buffer(slot & Self::IDX_MASK).drop_in_place();
+                }
+            }
+        }
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T, const N: usize> {
+    inner: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Sender<'_, T, N> {
+    /// Sends data through the channel
+    ///
+    /// If the channel is full, its oldest element is overwritten and returned
+    pub fn send(&self, value: T) -> Option<T> {
+        let inner = self.inner;
+        let current_write = inner.write.load(atomic::Ordering::Relaxed);
+        let spare = inner.spare.get();
+
+        // SAFETY: the sender owns the spare buffer
+        unsafe {
+            inner.buffer(spare).write(value);
+        }
+
+        let slot = &inner.slots[current_write % N];
+        // Release: the buffer write above happens before the receiver takes the buffer
+        // Acquire: the receiver's accesses to the buffer it handed back to the ring happen before
+        // we access it
+        let previous = slot.swap(
+            Channel::<T, N>::pack(current_write, true, spare),
+            atomic::Ordering::AcqRel,
+        );
+        let previous_idx = previous & Channel::<T, N>::IDX_MASK;
+        inner.spare.set(previous_idx);
+
+        // Release: the slot swap above happens before the receiver observes the new `write`
+        inner.write.store(
+            Channel::<T, N>::advance(current_write, 1),
+            atomic::Ordering::Release,
+        );
+
+        if previous & Channel::<T, N>::FULL != 0 {
+            // SAFETY: the buffer holds the overwritten element and is now owned by the sender
+            Some(unsafe { inner.buffer(previous_idx).read() })
+        } else {
+            None
+        }
+    }
+}
+
+/// The receiver side of a channel
+pub struct Receiver<'a, T, const N: usize> {
+    inner: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Receiver<'_, T, N> {
+    /// Receives the oldest element in the channel
+    ///
+    /// Returns `Err(Lagged)` if elements were overwritten before they could be received; the
+    /// next call returns the oldest element that is still in the channel
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let inner = self.inner;
+        let current_read = inner.read.get();
+
+        // Acquire: synchronizes with the Release `write` store in `send` so the slot swap that
+        // precedes it is visible below
+        let acquired_write = inner.write.load(atomic::Ordering::Acquire);
+        let len = Channel::<T, N>::distance(current_read, acquired_write);
+
+        if len == 0 {
+            return Err(RecvError::Empty);
+        }
+
+        if len > N {
+            let oldest = Channel::<T, N>::retreat(acquired_write, N);
+            inner.read.set(oldest);
+            return Err(RecvError::Lagged(Channel::<T, N>::distance(
+                current_read,
+                oldest,
+            )));
+        }
+
+        let slot = &inner.slots[current_read % N];
+        let current = slot.load(atomic::Ordering::Relaxed);
+        let held = inner.held.get();
+        // Acquire: the sender's write to the taken buffer happens before we read it
+        // Release: our accesses to the held buffer happen before the sender reuses it
+        if !Channel::<T, N>::holds(current, current_read)
+            || slot
+                .compare_exchange(
+                    current,
+                    Channel::<T, N>::pack(current_read, false, held),
+                    atomic::Ordering::AcqRel,
+                    atomic::Ordering::Relaxed,
+                )
+                .is_err()
+        {
+            // the element was overwritten after `write` was loaded, possibly by a send that is
+            // still in progress; skip it instead of waiting for the sender to update `write`
+            inner.read.set(Channel::<T, N>::advance(current_read, 1));
+            return Err(RecvError::Lagged(1));
+        }
+
+        let idx = current & Channel::<T, N>::IDX_MASK;
+        inner.held.set(idx);
+        inner.read.set(Channel::<T, N>::advance(current_read, 1));
+
+        // SAFETY: the buffer holds the element at `current_read` and is now owned by the receiver
+        Ok(unsafe { inner.buffer(idx).read() })
+    }
+}
+
+/// The error returned by `Receiver::recv`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// The channel is empty
+    Empty,
+    /// The receiver fell behind and this many elements were overwritten before they could be
+    /// received
+    Lagged(usize),
+}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T, const N: usize> Send for Sender<'_, T, N> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T, const N: usize> Send for Receiver<'_, T, N> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_order() {
+        let mut channel = Channel::<i32, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+        assert_eq!(None, sender.send(1));
+        assert_eq!(None, sender.send(2));
+        assert_eq!(Ok(1), receiver.recv());
+        assert_eq!(Ok(2), receiver.recv());
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn overwrites_oldest() {
+        let mut channel = Channel::<i32, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        for value in 0..3 {
+            assert_eq!(None, sender.send(value));
+        }
+        assert_eq!(Some(0), sender.send(3));
+        assert_eq!(Some(1), sender.send(4));
+
+        assert_eq!(Err(RecvError::Lagged(2)), receiver.recv());
+        assert_eq!(Ok(2), receiver.recv());
+        assert_eq!(Ok(3), receiver.recv());
+
+        assert_eq!(None, sender.send(5));
+        assert_eq!(Ok(4), receiver.recv());
+        assert_eq!(Ok(5), receiver.recv());
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn recv_does_not_wait_for_a_preempted_send() {
+        let mut channel = Channel::<i32, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        assert_eq!(None, sender.send(1));
+        assert_eq!(None, sender.send(2));
+
+        // a send that is preempted between the slot swap and the `write` store
+        let inner = sender.inner;
+        let spare = inner.spare.get();
+        // SAFETY: the sender owns the spare buffer
+        unsafe {
+            inner.buffer(spare).write(3);
+        }
+        let previous = inner.slots[0].swap(
+            Channel::<i32, 2>::pack(2, true, spare),
+            atomic::Ordering::AcqRel,
+        );
+        inner.spare.set(previous & Channel::<i32, 2>::IDX_MASK);
+
+        assert_eq!(Err(RecvError::Lagged(1)), receiver.recv());
+        assert_eq!(Ok(2), receiver.recv());
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+
+        inner.write.store(3, atomic::Ordering::Release);
+        assert_eq!(Ok(3), receiver.recv());
+    }
+
+    #[test]
+    fn cursors_wrap_around_with_non_power_of_two_capacity() {
+        let mut channel = Channel::<usize, 3>::new();
+        let start = Channel::<usize, 3>::WRAP - 2;
+        *channel.write.get_mut() = start;
+        channel.read.set(start);
+        let (sender, receiver) = channel.split();
+
+        for value in 0..3 {
+            assert_eq!(None, sender.send(value));
+        }
+        assert_eq!(Some(0), sender.send(3));
+
+        // the cursors wrap around while the channel is full
+        assert_eq!(Err(RecvError::Lagged(1)), receiver.recv());
+        for value in 1..4 {
+            assert_eq!(Ok(value), receiver.recv());
+        }
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+
+        for value in 4..10 {
+            assert_eq!(None, sender.send(value));
+            assert_eq!(Ok(value), receiver.recv());
+        }
+        assert_eq!(Err(RecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn dropping_the_channel_drops_pending_items() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut channel = Channel::<_, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        assert!(sender.send(value.clone()).is_none());
+        assert!(sender.send(value.clone()).is_none());
+        assert!(receiver.recv().is_ok());
+        assert!(sender.send(value.clone()).is_none());
+        assert_eq!(3, Rc::strong_count(&value));
+
+        drop(channel);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn concurrent_overwrites() {
+        const MESSAGES: usize = 100_000;
+
+        let mut channel = Channel::<usize, 4>::new();
+        let (sender, receiver) = channel.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for value in 0..MESSAGES {
+                    sender.send(value);
+                }
+            });
+
+            let mut expected = 0;
+            while expected < MESSAGES {
+                match receiver.recv() {
+                    Ok(value) => {
+                        assert_eq!(expected, value);
+                        expected += 1;
+                    }
+                    Err(RecvError::Lagged(missed)) => expected += missed,
+                    Err(RecvError::Empty) => {}
+                }
+            }
+        });
+    }
+}
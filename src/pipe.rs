@@ -0,0 +1,412 @@
+//! A fixed-capacity, single-producer, single-consumer (SPSC) byte stream
+//!
+//! A `Pipe` is a `spsc::Channel<u8, N>` that is written and read through slices of bytes. Each
+//! write and read copies a single contiguous region of the ring so either may be partial; call
+//! them again for the rest
+//!
+//! With the `embedded-io` feature, `Writer` and `Reader` implement the blocking `embedded_io`
+//! traits, spinning while the pipe is full or empty. With the `embedded-io-async` feature, they
+//! also implement the `embedded_io_async` traits and wake each other's task, through the wakers
+//! of the channel, instead
+
+use crate::spsc;
+
+/// A fixed-capacity, single-producer, single-consumer (SPSC) byte stream
+pub struct Pipe<const N: usize> {
+    channel: spsc::Channel<u8, N>,
+}
+
+impl<const N: usize> Pipe<N> {
+    /// Creates a new pipe
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            channel: spsc::Channel::new(),
+        }
+    }
+
+    /// Splits this pipe into writer and reader parts
+    ///
+    /// The pipe is mutably borrowed for as long as either part is live
+    pub fn split(&mut self) -> (Writer<'_>, Reader<'_>) {
+        let (sender, receiver) = self.channel.split();
+
+        (Writer { sender }, Reader { receiver })
+    }
+}
+
+/// The writer side of a pipe
+pub struct Writer<'a> {
+    sender: spsc::Sender<'a, u8>,
+}
+
+impl Writer<'_> {
+    /// Copies bytes from `buf` into the free space of the pipe that is contiguous in memory
+    ///
+    /// Returns how many bytes were written, which is `0` if the pipe is observed as being full
+    pub fn try_write(&mut self, buf: &[u8]) -> usize {
+        let Some(mut grant) = self.sender.grant_slice() else {
+            return 0;
+        };
+
+        let len = buf.len().min(grant.len());
+        for (slot, byte) in grant.iter_mut().zip(&buf[..len]) {
+            slot.write(*byte);
+        }
+        // SAFETY: the first `len` slots of the grant were initialized above
+        unsafe {
+            grant.commit(len);
+        }
+
+        len
+    }
+
+    /// Returns `true` if the reader has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Returns the total number of bytes the pipe can hold
+    pub fn capacity(&self) -> usize {
+        self.sender.capacity()
+    }
+}
+
+/// The reader side of a pipe
+pub struct Reader<'a> {
+    receiver: spsc::Receiver<'a, u8>,
+}
+
+impl Reader<'_> {
+    /// Copies bytes into `buf` from the oldest bytes of the pipe that are contiguous in memory
+    ///
+    /// Returns how many bytes were read, which is `0` if the pipe is observed as being empty
+    pub fn try_read(&mut self, buf: &mut [u8]) -> usize {
+        let Some(grant) = self.receiver.peek_grant_slice() else {
+            return 0;
+        };
+
+        let len = buf.len().min(grant.len());
+        buf[..len].copy_from_slice(&grant[..len]);
+        grant.release(len);
+
+        len
+    }
+
+    /// Returns `true` if the writer has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.receiver.is_closed()
+    }
+
+    /// Returns the total number of bytes the pipe can hold
+    pub fn capacity(&self) -> usize {
+        self.receiver.capacity()
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl Writer<'_> {
+    /// Returns `Some` once the write is done: `buf` is empty, some bytes were written or the
+    /// reader is gone
+    fn write_or_closed(&mut self, buf: &[u8]) -> Option<Result<usize, embedded_io::ErrorKind>> {
+        if buf.is_empty() {
+            return Some(Ok(0));
+        }
+
+        if self.is_closed() {
+            return Some(Err(embedded_io::ErrorKind::BrokenPipe));
+        }
+
+        match self.try_write(buf) {
+            0 => None,
+            len => Some(Ok(len)),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl Reader<'_> {
+    /// Returns `Some` once the read is done: `buf` is empty, some bytes were read or the writer
+    /// is gone and the pipe has been drained (EOF)
+    fn read_or_eof(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if buf.is_empty() {
+            return Some(0);
+        }
+
+        // checked first so that the bytes written before the writer was dropped are read below
+        let closed = self.is_closed();
+
+        match self.try_read(buf) {
+            0 if closed => Some(0),
+            0 => None,
+            len => Some(len),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for Writer<'_> {
+    type Error = embedded_io::ErrorKind;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for Writer<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        loop {
+            if let Some(outcome) = self.write_or_closed(buf) {
+                return outcome;
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        // written bytes are immediately visible to the reader
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::WriteReady for Writer<'_> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.is_closed() || !self.sender.is_full())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for Reader<'_> {
+    type Error = embedded_io::ErrorKind;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            if let Some(len) = self.read_or_eof(buf) {
+                return Ok(len);
+            }
+
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ReadReady for Reader<'_> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.is_closed() || !self.receiver.is_empty())
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl embedded_io_async::Write for Writer<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        core::future::poll_fn(|cx| {
+            if let Some(outcome) = self.write_or_closed(buf) {
+                return core::task::Poll::Ready(outcome);
+            }
+
+            self.sender.register(cx.waker());
+
+            // the reader may have made room before the waker was registered
+            match self.write_or_closed(buf) {
+                Some(outcome) => core::task::Poll::Ready(outcome),
+                None => core::task::Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        // written bytes are immediately visible to the reader
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl embedded_io_async::Read for Reader<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        core::future::poll_fn(|cx| {
+            if let Some(len) = self.read_or_eof(buf) {
+                return core::task::Poll::Ready(Ok(len));
+            }
+
+            self.receiver.register(cx.waker());
+
+            // the writer may have written before the waker was registered
+            match self.read_or_eof(buf) {
+                Some(len) => core::task::Poll::Ready(Ok(len)),
+                None => core::task::Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_contiguous_transfers() {
+        let mut pipe = Pipe::<5>::new();
+        let (mut writer, mut reader) = pipe.split();
+
+        assert_eq!(3, writer.try_write(b"abc"));
+        let mut buf = [0; 2];
+        assert_eq!(2, reader.try_read(&mut buf));
+        assert_eq!(b"ab", &buf);
+
+        // only the bytes up to the end of the ring are written
+        assert_eq!(2, writer.try_write(b"defg"));
+        assert_eq!(2, writer.try_write(b"fg"));
+        assert_eq!(0, writer.try_write(b"h"));
+
+        let mut buf = [0; 8];
+        assert_eq!(3, reader.try_read(&mut buf));
+        assert_eq!(b"cde", &buf[..3]);
+        assert_eq!(2, reader.try_read(&mut buf));
+        assert_eq!(b"fg", &buf[..2]);
+        assert_eq!(0, reader.try_read(&mut buf));
+    }
+
+    #[test]
+    fn works_with_non_power_of_two() {
+        let mut pipe = Pipe::<3>::new();
+        let (mut writer, mut reader) = pipe.split();
+
+        let mut buf = [0; 2];
+        for round in 0..10u8 {
+            let bytes = [round, round + 1];
+
+            // the second write is only needed when the first one reaches the end of the ring
+            let mut len = writer.try_write(&bytes);
+            len += writer.try_write(&bytes[len..]);
+            assert_eq!(2, len);
+
+            let mut len = reader.try_read(&mut buf);
+            len += reader.try_read(&mut buf[len..]);
+            assert_eq!(2, len);
+            assert_eq!(bytes, buf);
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn blocking_io() {
+        use embedded_io::{Read, ReadReady, Write, WriteReady};
+
+        let mut pipe = Pipe::<4>::new();
+        let (mut writer, mut reader) = pipe.split();
+        let message = b"hello, world! this does not fit in the pipe";
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                assert_eq!(Ok(true), writer.write_ready());
+                writer.write_all(message).unwrap();
+            });
+
+            let mut received = Vec::new();
+            let mut buf = [0; 3];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(len) => received.extend_from_slice(&buf[..len]),
+                    Err(e) => panic!("{e:?}"),
+                }
+            }
+
+            assert_eq!(&message[..], &received[..]);
+            assert_eq!(Ok(true), reader.read_ready());
+        });
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn writing_to_a_closed_pipe_fails() {
+        use embedded_io::Write;
+
+        let mut pipe = Pipe::<4>::new();
+        let (mut writer, reader) = pipe.split();
+
+        drop(reader);
+        assert_eq!(Err(embedded_io::ErrorKind::BrokenPipe), writer.write(b"a"));
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn async_read_is_woken_by_write() {
+        use std::pin::pin;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicUsize;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Counter(AtomicUsize);
+
+        impl Wake for Counter {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut pipe = Pipe::<4>::new();
+        let (mut writer, mut reader) = pipe.split();
+        let mut buf = [0; 4];
+
+        {
+            let mut read = pin!(embedded_io_async::Read::read(&mut reader, &mut buf));
+            assert!(read.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(0, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+
+            assert_eq!(2, writer.try_write(b"hi"));
+            assert_eq!(1, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+            assert_eq!(Poll::Ready(Ok(2)), read.as_mut().poll(&mut cx));
+        }
+        assert_eq!(b"hi", &buf[..2]);
+
+        let mut read = pin!(embedded_io_async::Read::read(&mut reader, &mut buf));
+        assert!(read.as_mut().poll(&mut cx).is_pending());
+        drop(writer);
+        assert_eq!(2, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+        assert_eq!(Poll::Ready(Ok(0)), read.as_mut().poll(&mut cx));
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn async_write_is_woken_by_read() {
+        use std::pin::pin;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicUsize;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Counter(AtomicUsize);
+
+        impl Wake for Counter {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut pipe = Pipe::<2>::new();
+        let (mut writer, mut reader) = pipe.split();
+        assert_eq!(2, writer.try_write(b"hi"));
+
+        let mut write = pin!(embedded_io_async::Write::write(&mut writer, b"!"));
+        assert!(write.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(0, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+
+        let mut buf = [0; 1];
+        assert_eq!(1, reader.try_read(&mut buf));
+        assert_eq!(1, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+        assert_eq!(Poll::Ready(Ok(1)), write.as_mut().poll(&mut cx));
+    }
+}
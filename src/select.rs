@@ -0,0 +1,230 @@
+//! Receiving from whichever of several SPSC receivers has data
+//!
+//! A `Select` checks a fixed set of receivers, either always in the same order, so the first
+//! receivers have priority, or round-robin, so that a busy receiver cannot starve the others
+
+#[cfg(feature = "async")]
+use core::future;
+#[cfg(feature = "async")]
+use core::task::Poll;
+
+use crate::spsc::{Receiver, TryRecvError};
+
+/// Receives from the first of a fixed set of receivers that has data
+pub struct Select<'r, 'a, T, const N: usize> {
+    receivers: [&'r mut Receiver<'a, T>; N],
+    /// The index of the receiver that is checked first
+    start: usize,
+    round_robin: bool,
+}
+
+impl<'r, 'a, T, const N: usize> Select<'r, 'a, T, N> {
+    /// Creates a selector that always checks the receivers in order so the receivers that come
+    /// first have priority
+    pub fn biased(receivers: [&'r mut Receiver<'a, T>; N]) -> Self {
+        Self::new(receivers, false)
+    }
+
+    /// Creates a selector that starts checking from the receiver that follows the one that last
+    /// produced data
+    pub fn round_robin(receivers: [&'r mut Receiver<'a, T>; N]) -> Self {
+        Self::new(receivers, true)
+    }
+
+    fn new(receivers: [&'r mut Receiver<'a, T>; N], round_robin: bool) -> Self {
+        const {
+            assert!(N > 0, "there must be at least one receiver");
+        }
+
+        Self {
+            receivers,
+            start: 0,
+            round_robin,
+        }
+    }
+
+    /// Receives data from the first receiver that has some
+    ///
+    /// Returns the index of that receiver along with the data. Returns `Disconnected` only once
+    /// every sender is gone and every channel has been drained
+    pub fn try_recv(&mut self) -> Result<(usize, T), TryRecvError> {
+        let mut disconnected = true;
+
+        for offset in 0..N {
+            let index = (self.start + offset) % N;
+
+            match self.receivers[index].recv() {
+                Ok(value) => {
+                    if self.round_robin {
+                        self.start = (index + 1) % N;
+                    }
+
+                    return Ok((index, value));
+                }
+                Err(TryRecvError::Empty) => disconnected = false,
+                Err(TryRecvError::Disconnected) => {}
+            }
+        }
+
+        Err(if disconnected {
+            TryRecvError::Disconnected
+        } else {
+            TryRecvError::Empty
+        })
+    }
+
+    /// Waits until a receiver has data and receives it
+    ///
+    /// The task's waker is registered with all the receivers. Returns `None` once every sender is
+    /// gone and every channel has been drained
+    #[cfg(feature = "async")]
+    pub async fn recv(&mut self) -> Option<(usize, T)> {
+        future::poll_fn(|cx| {
+            for receiver in &mut self.receivers {
+                receiver.register(cx.waker());
+            }
+
+            // a sender may have sent data before the waker was registered so check after
+            // registering
+            match self.try_recv() {
+                Ok(received) => Poll::Ready(Some(received)),
+                Err(TryRecvError::Empty) => Poll::Pending,
+                Err(TryRecvError::Disconnected) => Poll::Ready(None),
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spsc::Channel;
+
+    #[test]
+    fn biased_prefers_the_first_receivers() {
+        let mut high = Channel::<i32, 4>::new();
+        let mut low = Channel::<i32, 4>::new();
+        let (high_sender, mut high_receiver) = high.split();
+        let (low_sender, mut low_receiver) = low.split();
+
+        let mut select = Select::biased([&mut high_receiver, &mut low_receiver]);
+        assert_eq!(Err(TryRecvError::Empty), select.try_recv());
+
+        assert_eq!(Ok(()), low_sender.send(1));
+        assert_eq!(Ok(()), low_sender.send(2));
+        assert_eq!(Ok(()), high_sender.send(3));
+
+        assert_eq!(Ok((0, 3)), select.try_recv());
+        assert_eq!(Ok((1, 1)), select.try_recv());
+        assert_eq!(Ok(()), high_sender.send(4));
+        assert_eq!(Ok((0, 4)), select.try_recv());
+        assert_eq!(Ok((1, 2)), select.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), select.try_recv());
+    }
+
+    #[test]
+    fn round_robin_takes_turns() {
+        let mut a = Channel::<i32, 4>::new();
+        let mut b = Channel::<i32, 4>::new();
+        let mut c = Channel::<i32, 4>::new();
+        let (a_sender, mut a_receiver) = a.split();
+        let (b_sender, mut b_receiver) = b.split();
+        let (c_sender, mut c_receiver) = c.split();
+
+        for value in 0..2 {
+            assert_eq!(Ok(()), a_sender.send(value));
+            assert_eq!(Ok(()), c_sender.send(10 + value));
+        }
+        assert_eq!(Ok(()), b_sender.send(20));
+
+        let mut select = Select::round_robin([&mut a_receiver, &mut b_receiver, &mut c_receiver]);
+        assert_eq!(Ok((0, 0)), select.try_recv());
+        assert_eq!(Ok((1, 20)), select.try_recv());
+        assert_eq!(Ok((2, 10)), select.try_recv());
+        assert_eq!(Ok((0, 1)), select.try_recv());
+        assert_eq!(Ok((2, 11)), select.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), select.try_recv());
+    }
+
+    #[test]
+    fn disconnected_once_all_senders_are_gone() {
+        let mut a = Channel::<i32, 4>::new();
+        let mut b = Channel::<i32, 4>::new();
+        let (a_sender, mut a_receiver) = a.split();
+        let (b_sender, mut b_receiver) = b.split();
+
+        let mut select = Select::biased([&mut a_receiver, &mut b_receiver]);
+        drop(a_sender);
+        assert_eq!(Err(TryRecvError::Empty), select.try_recv());
+
+        assert_eq!(Ok(()), b_sender.send(1));
+        drop(b_sender);
+        assert_eq!(Ok((1, 1)), select.try_recv());
+        assert_eq!(Err(TryRecvError::Disconnected), select.try_recv());
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn recv_is_woken_by_any_sender() {
+        use std::pin::pin;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicUsize;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Counter(AtomicUsize);
+
+        impl Wake for Counter {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        let counter = Arc::new(Counter(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut a = Channel::<i32, 4>::new();
+        let mut b = Channel::<i32, 4>::new();
+        let (a_sender, mut a_receiver) = a.split();
+        let (b_sender, mut b_receiver) = b.split();
+        let mut select = Select::biased([&mut a_receiver, &mut b_receiver]);
+
+        {
+            let mut recv = pin!(select.recv());
+            assert!(recv.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(0, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+
+            assert_eq!(Ok(()), b_sender.send(1));
+            assert_eq!(1, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+            assert_eq!(Poll::Ready(Some((1, 1))), recv.as_mut().poll(&mut cx));
+        }
+
+        {
+            let mut recv = pin!(select.recv());
+            assert!(recv.as_mut().poll(&mut cx).is_pending());
+
+            assert_eq!(Ok(()), a_sender.send(2));
+            assert_eq!(2, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+            assert_eq!(Poll::Ready(Some((0, 2))), recv.as_mut().poll(&mut cx));
+        }
+
+        let mut recv = pin!(select.recv());
+        assert!(recv.as_mut().poll(&mut cx).is_pending());
+        drop(a_sender);
+        drop(b_sender);
+        assert_eq!(4, counter.0.load(core::sync::atomic::Ordering::Relaxed));
+        assert_eq!(Poll::Ready(None), recv.as_mut().poll(&mut cx));
+    }
+
+    #[test]
+    fn check_select_is_send() {
+        is_send::<Select<i32, 2>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
@@ -0,0 +1,383 @@
+//! A sequence lock: a value with a single writer and any number of readers that never block the
+//! writer
+//!
+//! The writer bumps a sequence number before and after updating the value; readers copy the value
+//! and retry if the sequence number was odd or changed meanwhile. Readers and the writer may access
+//! the value at the same time so both copy it with relaxed atomic operations, which makes the
+//! torn copies that readers throw away data race free
+//!
+//! A reader that preempts the writer, e.g. in an interrupt handler, would retry forever; it must
+//! use `Reader::try_read` instead
+//!
+//! The value is copied as plain bytes so its type must implement `NoUninit`. Primitives, arrays
+//! and tuples do; a struct can if it's `#[repr(C)]`, its fields implement `NoUninit` and it has no
+//! padding between or after them, e.g. a `u64` followed by two `f32`s. The `repr_c_struct` test
+//! shows such an implementation
+
+use core::cell::UnsafeCell;
+use core::hint;
+use core::mem::{self, MaybeUninit};
+use core::sync::atomic::{self, AtomicU8, AtomicUsize};
+
+/// A value that is written by a single writer and read by any number of readers
+pub struct SeqLock<T> {
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> SeqLock<T>
+where
+    T: NoUninit,
+{
+    /// Creates a new lock that holds `value`
+    pub const fn new(value: T) -> Self {
+        const {
+            assert!(T::NO_PADDING, "the value type has padding");
+        }
+
+        Self {
+            seq: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+
+    /// Splits this lock into its writer and a reader
+    ///
+    /// The reader can be copied to get more readers. The lock is mutably borrowed for as long as
+    /// any part is live so there's a single writer
+    pub fn split(&mut self) -> (Writer<'_, T>, Reader<'_, T>) {
+        let lock = &*self;
+
+        (Writer { lock }, Reader { lock })
+    }
+}
+
+/// The writer side of a lock
+pub struct Writer<'a, T> {
+    lock: &'a SeqLock<T>,
+}
+
+impl<T> Writer<'_, T>
+where
+    T: NoUninit,
+{
+    /// Replaces the value
+    ///
+    /// This never waits on the readers
+    pub fn write(&mut self, value: T) {
+        let seq = self.lock.seq.load(atomic::Ordering::Relaxed);
+        // an odd sequence number tells readers that a write is in progress
+        self.lock
+            .seq
+            .store(seq.wrapping_add(1), atomic::Ordering::Relaxed);
+        // Release: the odd sequence number is visible before any of the value stores below
+        atomic::fence(atomic::Ordering::Release);
+
+        let value = MaybeUninit::new(value);
+        // SAFETY: `NoUninit` ensures all the bytes of `value` are initialized; the lock's value is
+        // only accessed atomically
+        unsafe {
+            copy_atomic(value.as_ptr(), self.lock.value.get().cast());
+        }
+
+        // Release: the value stores above happen before a reader that observes the new sequence
+        // number
+        self.lock
+            .seq
+            .store(seq.wrapping_add(2), atomic::Ordering::Release);
+    }
+
+    /// Returns the value
+    pub fn read(&self) -> T {
+        Reader { lock: self.lock }.read()
+    }
+}
+
+/// A reader side of a lock
+pub struct Reader<'a, T> {
+    lock: &'a SeqLock<T>,
+}
+
+impl<T> Reader<'_, T>
+where
+    T: NoUninit,
+{
+    /// Returns the value, retrying while a write is in progress
+    pub fn read(&self) -> T {
+        loop {
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+
+            hint::spin_loop();
+        }
+    }
+
+    /// Returns the value or `None` if a write was in progress
+    pub fn try_read(&self) -> Option<T> {
+        // Acquire: synchronizes with the Release `seq` store at the end of `write`
+        let seq = self.lock.seq.load(atomic::Ordering::Acquire);
+        if seq % 2 == 1 {
+            return None;
+        }
+
+        let mut value = MaybeUninit::<T>::uninit();
+        // SAFETY: the lock's value is initialized and only accessed atomically
+        unsafe {
+            copy_atomic(self.lock.value.get().cast(), value.as_mut_ptr());
+        }
+
+        // Acquire: the value loads above happen before the `seq` load below; if any of them
+        // observed a store of a later write then so does the `seq` load
+        atomic::fence(atomic::Ordering::Acquire);
+        if self.lock.seq.load(atomic::Ordering::Relaxed) != seq {
+            // the copy may be torn
+            return None;
+        }
+
+        // SAFETY: no write happened during the copy so it's a copy of a valid value
+        Some(unsafe { value.assume_init() })
+    }
+}
+
+impl<T> Clone for Reader<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Reader<'_, T> {}
+
+/// Copies a `T` from `src` to `dst` with relaxed atomic operations, a word at a time when
+/// possible
+///
+/// # Safety
+/// - All the bytes at `src` must be initialized
+/// - `src` and `dst` must be valid and aligned for `T`; all concurrent accesses to them must be
+///   atomic
+unsafe fn copy_atomic<T>(src: *const T, dst: *mut T) {
+    let mut offset = 0;
+
+    if mem::align_of::<T>() >= mem::align_of::<AtomicUsize>() {
+        while offset + mem::size_of::<AtomicUsize>() <= mem::size_of::<T>() {
+            // SAFETY: in bounds and aligned, as `offset` is a multiple of the word size, as per
+            // the caller contract
+            unsafe {
+                let src = AtomicUsize::from_ptr(src.byte_add(offset).cast::<usize>().cast_mut());
+                let dst = AtomicUsize::from_ptr(dst.byte_add(offset).cast::<usize>());
+                dst.store(
+                    src.load(atomic::Ordering::Relaxed),
+                    atomic::Ordering::Relaxed,
+                );
+            }
+            offset += mem::size_of::<AtomicUsize>();
+        }
+    }
+
+    while offset < mem::size_of::<T>() {
+        // SAFETY: in bounds as per the caller contract; bytes have no alignment requirement
+        unsafe {
+            let src = AtomicU8::from_ptr(src.byte_add(offset).cast::<u8>().cast_mut());
+            let dst = AtomicU8::from_ptr(dst.byte_add(offset).cast::<u8>());
+            dst.store(
+                src.load(atomic::Ordering::Relaxed),
+                atomic::Ordering::Relaxed,
+            );
+        }
+        offset += 1;
+    }
+}
+
+/// Types whose bytes are all initialized, i.e. that have no padding
+///
+/// `SeqLock` copies values as plain bytes; reading the padding of a value, which is uninitialized,
+/// would be undefined behavior
+///
+/// # Safety
+/// - The type must have no padding, including the padding in any of its fields, unless
+///   `NO_PADDING` is `false`
+pub unsafe trait NoUninit: Copy {
+    /// `false` if the type has padding after all, which `SeqLock::new` rejects at compile time
+    ///
+    /// Generic implementations, e.g. the one for tuples, compute it from the layout
+    const NO_PADDING: bool = true;
+}
+
+macro_rules! impl_no_uninit {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: primitive types have no padding
+            unsafe impl NoUninit for $ty {}
+        )*
+    };
+}
+
+impl_no_uninit!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+// SAFETY: the elements of an array are laid out without padding between them
+unsafe impl<T, const N: usize> NoUninit for [T; N]
+where
+    T: NoUninit,
+{
+    const NO_PADDING: bool = T::NO_PADDING;
+}
+
+macro_rules! impl_no_uninit_tuple {
+    ($($field:ident),+) => {
+        // SAFETY: the fields have no padding and `NO_PADDING` is `false` unless their sizes add up
+        // to the size of the tuple, i.e. unless there's no padding between or after them
+        unsafe impl<$($field),+> NoUninit for ($($field,)+)
+        where
+            $($field: NoUninit),+
+        {
+            const NO_PADDING: bool = $($field::NO_PADDING &&)+
+                mem::size_of::<Self>() == 0 $(+ mem::size_of::<$field>())+;
+        }
+    };
+}
+
+impl_no_uninit_tuple!(A);
+impl_no_uninit_tuple!(A, B);
+impl_no_uninit_tuple!(A, B, C);
+impl_no_uninit_tuple!(A, B, C, D);
+impl_no_uninit_tuple!(A, B, C, D, E);
+impl_no_uninit_tuple!(A, B, C, D, E, F);
+
+// SAFETY: the handles only copy values out of or into the lock so sharing it across threads
+// requires the value to be Send
+unsafe impl<T> Sync for SeqLock<T> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_latest_value() {
+        let mut lock = SeqLock::new([0u8; 3]);
+        let (mut writer, reader) = lock.split();
+        let other_reader = reader;
+
+        assert_eq!([0; 3], reader.read());
+        writer.write([1, 2, 3]);
+        assert_eq!([1, 2, 3], reader.read());
+        assert_eq!(Some([1, 2, 3]), other_reader.try_read());
+        assert_eq!([1, 2, 3], writer.read());
+    }
+
+    #[test]
+    fn try_read_fails_during_a_write() {
+        let mut lock = SeqLock::new(42u64);
+        *lock.seq.get_mut() = 1;
+
+        let (_writer, reader) = lock.split();
+        assert_eq!(None, reader.try_read());
+    }
+
+    #[test]
+    fn no_tearing() {
+        const WRITES: u64 = 100_000;
+        const READERS: usize = 2;
+
+        let mut lock = SeqLock::new([0u64; 5]);
+        let (mut writer, reader) = lock.split();
+
+        std::thread::scope(|s| {
+            for _ in 0..READERS {
+                s.spawn(move || {
+                    let mut latest = 0;
+                    while latest != WRITES {
+                        let values = reader.read();
+                        assert!(values.iter().all(|value| *value == values[0]));
+                        assert!(values[0] >= latest);
+                        latest = values[0];
+                        std::thread::yield_now();
+                    }
+                });
+            }
+
+            for value in 1..=WRITES {
+                writer.write([value; 5]);
+            }
+        });
+    }
+
+    #[test]
+    fn repr_c_struct() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C)]
+        struct Telemetry {
+            uptime_ms: u64,
+            voltage: f32,
+            current: f32,
+        }
+
+        // SAFETY: the fields have no padding; `repr(C)` lays them out in order and each one starts
+        // where the previous one ends, as does the end of the struct
+        unsafe impl NoUninit for Telemetry {}
+
+        let mut lock = SeqLock::new(Telemetry {
+            uptime_ms: 0,
+            voltage: 0.0,
+            current: 0.0,
+        });
+        let (mut writer, reader) = lock.split();
+
+        let telemetry = Telemetry {
+            uptime_ms: 1,
+            voltage: 3.3,
+            current: 0.1,
+        };
+        writer.write(telemetry);
+        assert_eq!(telemetry, reader.read());
+    }
+
+    #[test]
+    fn tuples_with_padding_are_rejected() {
+        assert!(no_padding::<(u32, u32)>());
+        assert!(no_padding::<([u8; 3], u8, u16)>());
+        assert!(!no_padding::<(u8, u32)>());
+        assert!(!no_padding::<[(u16, u8); 2]>());
+
+        let mut lock = SeqLock::new((1u32, 2u32));
+        let (mut writer, reader) = lock.split();
+        writer.write((3, 4));
+        assert_eq!((3, 4), reader.read());
+    }
+
+    #[test]
+    fn check_writer_and_reader_are_send() {
+        is_send::<Writer<u32>>();
+        is_send::<Reader<u32>>();
+    }
+
+    fn no_padding<T>() -> bool
+    where
+        T: NoUninit,
+    {
+        T::NO_PADDING
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
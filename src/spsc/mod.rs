@@ -0,0 +1,1902 @@
+//! A fixed-capacity, single-producer, single-consumer (SPSC) channel
+
+use core::cell::{Cell, UnsafeCell};
+use core::marker::PhantomData;
+use core::mem::{self, MaybeUninit};
+use core::sync::atomic::{self, AtomicBool, AtomicU32};
+#[cfg(feature = "async")]
+use core::task::Waker;
+use core::{ops, ptr, slice};
+
+use crate::vec::{Storage, Vec};
+use crate::wait::{Deadline, Wait};
+use crate::waker::AtomicWaker;
+
+pub mod priority;
+pub mod shared;
+
+/// The largest number of elements a channel can hold
+///
+/// Cursors are 32-bit wide and must be able to count up to twice the capacity
+pub const MAX_CAPACITY: usize = 1 << 30;
+
+/// A fixed-capacity, single-producer, single-consumer (SPSC) channel
+pub struct Channel<T, const N: usize> {
+    header: Header,
+    buf: [UnsafeCell<MaybeUninit<T>>; N],
+    wakers: Wakers,
+}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+            assert!(N <= MAX_CAPACITY, "capacity is too large");
+        }
+
+        Self {
+            header: Header::new(),
+            buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            wakers: Wakers::new(),
+        }
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// The channel is mutably borrowed for as long as either part is live so the channel can be
+    /// statically allocated, and split once, or live on the stack, e.g. within the scope of
+    /// `std::thread::scope`
+    pub fn split(&mut self) -> (Sender<'_, T>, Receiver<'_, T>) {
+        // the parts of a previous split may have closed the channel
+        *self.header.closed.get_mut() = false;
+        let inner = self.inner();
+
+        (Sender { inner }, Receiver { inner })
+    }
+
+    fn inner(&self) -> Inner<'_, T> {
+        Inner {
+            header: &self.header,
+            buf: &self.buf,
+            wakers: Some(&self.wakers),
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        self.inner().drop_items();
+    }
+}
+
+/// A single-producer, single-consumer (SPSC) channel whose capacity is picked at runtime
+///
+/// The elements live in a byte buffer, like the ones that back a `vec::Vec`, so the buffer can be
+/// sized from a configuration at boot or be taken from a pool
+pub struct StorageChannel<T, S>
+where
+    S: Storage,
+{
+    header: Header,
+    capacity: usize,
+    /// The offset from the start of `storage` to the first slot, as of the last split
+    offset: usize,
+    data: PhantomData<T>,
+    storage: S,
+    wakers: Wakers,
+}
+
+impl<T, S> StorageChannel<T, S>
+where
+    S: Storage,
+{
+    /// Creates a new channel that holds as many elements as fit in `storage`, up to
+    /// `MAX_CAPACITY`
+    ///
+    /// # Panics
+    /// If `storage` cannot hold a single element
+    pub fn new(storage: S) -> Self {
+        const {
+            assert!(
+                0 != mem::size_of::<T>(),
+                "zero-sized types are currently not supported"
+            );
+        }
+
+        let bytes = storage.as_uninit_bytes();
+        // leaves room to realign the slots if moving the channel changes the storage alignment
+        let capacity = bytes.len().saturating_sub(mem::align_of::<T>() - 1) / mem::size_of::<T>();
+        let capacity = capacity.min(MAX_CAPACITY);
+        assert!(capacity > 0, "storage cannot hold a single element");
+
+        Self {
+            header: Header::new(),
+            capacity,
+            offset: bytes.as_ptr().align_offset(mem::align_of::<T>()),
+            data: PhantomData,
+            storage,
+            wakers: Wakers::new(),
+        }
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// The channel is mutably borrowed for as long as either part is live
+    pub fn split(&mut self) -> (Sender<'_, T>, Receiver<'_, T>) {
+        // the parts of a previous split may have closed the channel
+        *self.header.closed.get_mut() = false;
+        let inner = self.inner();
+
+        (Sender { inner }, Receiver { inner })
+    }
+
+    fn inner(&mut self) -> Inner<'_, T> {
+        let bytes = self.storage.as_uninit_bytes_mut();
+        let offset = bytes.as_ptr().align_offset(mem::align_of::<T>());
+
+        if offset != self.offset {
+            // moving the channel moved the storage to an address with a different alignment
+            // SAFETY: `capacity` leaves room for the slots at any offset below the alignment of
+            // `T` so both ranges are within `bytes`
+            unsafe {
+                ptr::copy(
+                    bytes.as_ptr().add(self.offset),
+                    bytes.as_mut_ptr().add(offset),
+                    self.capacity * mem::size_of::<T>(),
+                );
+            }
+            self.offset = offset;
+        }
+
+        // SAFETY: the range is within `bytes` and aligned for `T`, which has the same layout as
+        // `UnsafeCell<MaybeUninit<T>>`; the mutable borrow of `self` keeps `storage` in place
+        let buf = unsafe {
+            slice::from_raw_parts(
+                bytes
+                    .as_mut_ptr()
+                    .add(offset)
+                    .cast::<UnsafeCell<MaybeUninit<T>>>(),
+                self.capacity,
+            )
+        };
+
+        Inner {
+            header: &self.header,
+            buf,
+            wakers: Some(&self.wakers),
+        }
+    }
+}
+
+impl<T, S> Drop for StorageChannel<T, S>
+where
+    S: Storage,
+{
+    fn drop(&mut self) {
+        self.inner().drop_items();
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T> {
+    inner: Inner<'a, T>,
+}
+
+impl<T> Sender<'_, T> {
+    /// Sends data through the channel
+    ///
+    /// Returns `Full` if the channel is observed as being full and `Disconnected` if the receiver
+    /// is gone; either way the value is handed back
+    pub fn send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.is_closed() {
+            return Err(TrySendError::Disconnected(value));
+        }
+
+        // SAFETY: `split` API ensures SPSC property
+        unsafe { self.inner.send(value) }.map_err(TrySendError::Full)
+    }
+
+    /// Sends data through the channel, waiting with `wait` while the channel is full
+    ///
+    /// Returns an `Err` with the value if the receiver is gone
+    pub fn send_blocking<W>(&self, value: T, wait: &mut W) -> Result<(), SendError<T>>
+    where
+        W: Wait,
+    {
+        self.send_timeout(value, || false, wait)
+            .map_err(|error| SendError(error.into_inner()))
+    }
+
+    /// Like `send_blocking` but gives up, returning `Full` with the value, once `deadline` has
+    /// expired
+    pub fn send_timeout<D, W>(
+        &self,
+        mut value: T,
+        deadline: D,
+        wait: &mut W,
+    ) -> Result<(), TrySendError<T>>
+    where
+        D: Deadline,
+        W: Wait,
+    {
+        loop {
+            match self.send(value) {
+                Ok(()) => {
+                    wait.notify();
+
+                    return Ok(());
+                }
+                Err(TrySendError::Full(full)) if !deadline.has_expired() => {
+                    value = full;
+
+                    if let (Some(waker), Some(wakers)) = (wait.waker(), self.inner.wakers) {
+                        wakers.sender.register(waker);
+
+                        // the receiver may have been dropped before the waker was registered
+                        if self.is_closed() {
+                            continue;
+                        }
+                    }
+
+                    wait.wait();
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns `true` if the receiver has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.inner.header.closed.load(atomic::Ordering::Acquire)
+    }
+
+    /// Registers `waker` to be woken when the receiver receives data or is dropped
+    ///
+    /// Channels in `shared` memory cannot hold a waker so `waker` is woken right away
+    #[cfg(feature = "async")]
+    pub(crate) fn register(&mut self, waker: &Waker) {
+        match self.inner.wakers {
+            Some(wakers) => wakers.sender.register(waker),
+            None => waker.wake_by_ref(),
+        }
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.buf.len()
+    }
+
+    /// Returns the number of elements in the channel as observed by the sender
+    ///
+    /// The receiver may concurrently remove elements so the actual number can only be lower
+    pub fn len(&self) -> usize {
+        self.capacity() - self.free()
+    }
+
+    /// Returns `true` if the channel is observed as being empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the channel is observed as being full
+    ///
+    /// If this returns `false`, the next `send` will not fail with `Full`; it fails with
+    /// `Disconnected` if the receiver is gone
+    pub fn is_full(&self) -> bool {
+        self.free() == 0
+    }
+
+    /// Returns the number of free slots in the channel as observed by the sender
+    ///
+    /// The receiver may concurrently remove elements so the actual number can only be higher
+    pub fn free(&self) -> usize {
+        // SAFETY: `split` API ensures SPSC property
+        unsafe { self.inner.free().1 }
+    }
+
+    /// Sends elements from `iter` until the channel is full or the iterator is exhausted
+    ///
+    /// All the elements are made available to the receiver at once. Returns how many elements
+    /// were sent; elements that did not fit are not taken out of the iterator so pass
+    /// `iter.by_ref()` to keep them
+    pub fn send_iter<I>(&mut self, iter: I) -> usize
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // SAFETY: `split` API ensures SPSC property; `&mut self` prevents `iter` from using this
+        // sender
+        unsafe { self.inner.send_iter(&mut iter.into_iter()) }
+    }
+
+    /// Copies as many elements from `values` as fit into the channel
+    ///
+    /// All the elements are made available to the receiver at once. Returns how many elements
+    /// were sent
+    pub fn send_slice(&self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        // SAFETY: `split` API ensures SPSC property
+        unsafe { self.inner.send_slice(values) }
+    }
+
+    /// Sends all the elements from `iter`, in batches, until the channel is full
+    ///
+    /// Returns the element that did not fit, or could not be sent because the receiver is gone,
+    /// as an `Err`; the elements after it are not taken out of the iterator so pass
+    /// `iter.by_ref()` to keep them
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), TrySendError<T>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut iter = iter.into_iter();
+
+        loop {
+            self.send_iter(iter.by_ref());
+
+            // the receiver may have made room since the batch was sent
+            let Some(value) = iter.next() else {
+                return Ok(());
+            };
+            self.send(value)?;
+        }
+    }
+
+    /// Grants write access to the next free slot of the channel
+    ///
+    /// Returns `None` if the channel is observed as being full
+    pub fn grant(&mut self) -> Option<WriteGrant<'_, T>> {
+        // SAFETY: `split` API ensures SPSC property
+        let (write, free) = unsafe { self.inner.writable() };
+
+        if free == 0 {
+            return None;
+        }
+
+        Some(WriteGrant {
+            inner: self.inner,
+            write,
+        })
+    }
+
+    /// Grants write access to all the free slots of the channel that are contiguous in memory,
+    /// starting from the next free slot
+    ///
+    /// Returns `None` if the channel is observed as being full
+    pub fn grant_slice(&mut self) -> Option<WriteSliceGrant<'_, T>> {
+        // SAFETY: `split` API ensures SPSC property
+        let (write, free) = unsafe { self.inner.writable() };
+
+        if free == 0 {
+            return None;
+        }
+
+        Some(WriteSliceGrant {
+            inner: self.inner,
+            write,
+            len: free,
+        })
+    }
+}
+
+impl<T> Drop for Sender<'_, T> {
+    fn drop(&mut self) {
+        // Release: the `write` cursor updates of this sender happen before the receiver observes
+        // the channel as closed
+        self.inner
+            .header
+            .closed
+            .store(true, atomic::Ordering::Release);
+
+        self.inner.wake_receiver();
+    }
+}
+
+/// The receiver side of a channel
+pub struct Receiver<'a, T> {
+    inner: Inner<'a, T>,
+}
+
+impl<T> Receiver<'_, T> {
+    /// Receives data through the channel
+    ///
+    /// Returns `Empty` if the channel is observed as being empty and `Disconnected` once the
+    /// sender is gone and the channel has been drained
+    pub fn recv(&self) -> Result<T, TryRecvError> {
+        // SAFETY: `split` API ensures SPSC property
+        if let Some(value) = unsafe { self.inner.recv() } {
+            return Ok(value);
+        }
+
+        if !self.is_closed() {
+            return Err(TryRecvError::Empty);
+        }
+
+        // the sender may have sent more data between the `recv` call and its drop
+        // SAFETY: `split` API ensures SPSC property
+        unsafe { self.inner.recv() }.ok_or(TryRecvError::Disconnected)
+    }
+
+    /// Receives data through the channel, waiting with `wait` while the channel is empty
+    ///
+    /// Returns an `Err` once the sender is gone and the channel has been drained
+    pub fn recv_blocking<W>(&self, wait: &mut W) -> Result<T, RecvError>
+    where
+        W: Wait,
+    {
+        self.recv_timeout(|| false, wait).map_err(|_| RecvError)
+    }
+
+    /// Like `recv_blocking` but gives up, returning `Empty`, once `deadline` has expired
+    pub fn recv_timeout<D, W>(&self, deadline: D, wait: &mut W) -> Result<T, TryRecvError>
+    where
+        D: Deadline,
+        W: Wait,
+    {
+        loop {
+            match self.recv() {
+                Ok(value) => {
+                    wait.notify();
+
+                    return Ok(value);
+                }
+                Err(TryRecvError::Empty) if !deadline.has_expired() => {
+                    if let (Some(waker), Some(wakers)) = (wait.waker(), self.inner.wakers) {
+                        wakers.receiver.register(waker);
+
+                        // the sender may have been dropped before the waker was registered
+                        if self.is_closed() {
+                            continue;
+                        }
+                    }
+
+                    wait.wait();
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns `true` if the sender has been dropped
+    ///
+    /// There may still be data left in the channel
+    pub fn is_closed(&self) -> bool {
+        self.inner.header.closed.load(atomic::Ordering::Acquire)
+    }
+
+    /// Registers `waker` to be woken when the sender sends data or is dropped
+    ///
+    /// Channels in `shared` memory cannot hold a waker so `waker` is woken right away
+    #[cfg(feature = "async")]
+    pub(crate) fn register(&mut self, waker: &Waker) {
+        match self.inner.wakers {
+            Some(wakers) => wakers.receiver.register(waker),
+            None => waker.wake_by_ref(),
+        }
+    }
+
+    /// Returns the total number of elements the channel can hold
+    pub fn capacity(&self) -> usize {
+        self.inner.buf.len()
+    }
+
+    /// Returns the number of elements in the channel as observed by the receiver
+    ///
+    /// The sender may concurrently add elements so the actual number can only be higher
+    pub fn len(&self) -> usize {
+        // SAFETY: `split` API ensures SPSC property
+        unsafe { self.inner.used().1 }
+    }
+
+    /// Returns `true` if the channel is observed as being empty
+    ///
+    /// If this returns `false`, the next `recv` will return an element
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the channel is observed as being full
+    pub fn is_full(&self) -> bool {
+        self.free() == 0
+    }
+
+    /// Returns the number of free slots in the channel as observed by the receiver
+    ///
+    /// The sender may concurrently add elements so the actual number can only be lower
+    pub fn free(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Returns a reference to the oldest element in the channel without removing it
+    ///
+    /// Returns `None` if the channel is observed as being empty
+    pub fn peek(&mut self) -> Option<&T> {
+        // SAFETY: `split` API ensures SPSC property
+        let (read, used) = unsafe { self.inner.used() };
+
+        if used == 0 {
+            return None;
+        }
+
+        // SAFETY: `&mut self` prevents `recv` from moving the element out while the reference is
+        // live; the slot is initialized due to the state of the `write` cursor
+        Some(unsafe { &*self.inner.slot_ptr(read) })
+    }
+
+    /// Returns a mutable reference to the oldest element in the channel without removing it
+    ///
+    /// Returns `None` if the channel is observed as being empty
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: `split` API ensures SPSC property
+        let (read, used) = unsafe { self.inner.used() };
+
+        if used == 0 {
+            return None;
+        }
+
+        // SAFETY: see `peek`
+        Some(unsafe { &mut *self.inner.slot_ptr(read) })
+    }
+
+    /// Moves as many elements as fit from the channel into `buf`, overwriting its contents
+    ///
+    /// All the slots are released back to the sender at once. Returns how many elements were
+    /// received; they are at the start of `buf`
+    pub fn recv_into(&mut self, buf: &mut [T]) -> usize {
+        let mut slots = buf.iter_mut();
+        // SAFETY: `split` API ensures SPSC property; `&mut self` prevents the destructors of the
+        // overwritten elements from using this receiver
+        unsafe {
+            self.inner.recv_with(slots.len(), |value| {
+                if let Some(slot) = slots.next() {
+                    *slot = value;
+                }
+            })
+        }
+    }
+
+    /// Moves all the elements currently in the channel out of it and passes them to `f`, in FIFO
+    /// order
+    ///
+    /// All the slots are released back to the sender at once. Returns how many elements were
+    /// received
+    pub fn drain<F>(&mut self, f: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        // SAFETY: `split` API ensures SPSC property; `&mut self` prevents `f` from using this
+        // receiver
+        unsafe { self.inner.recv_with(usize::MAX, f) }
+    }
+
+    /// Moves as many elements as fit into `vec`, in FIFO order
+    ///
+    /// All the slots are released back to the sender at once. Returns how many elements were
+    /// received
+    pub fn drain_to<S>(&mut self, vec: &mut Vec<T, S>) -> usize
+    where
+        S: Storage,
+    {
+        let max = vec.capacity() - vec.len();
+
+        // SAFETY: `split` API ensures SPSC property; the closure does not access the channel
+        unsafe {
+            self.inner.recv_with(max, |value| {
+                if vec.push(value).is_err() {
+                    unreachable!("`max` elements fit into `vec`");
+                }
+            })
+        }
+    }
+
+    /// Returns an iterator that receives data until the channel is observed as being empty
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+
+    /// Grants access to the oldest element in the channel without moving it out of the channel
+    ///
+    /// Returns `None` if the channel is observed as being empty
+    pub fn peek_grant(&mut self) -> Option<ReadGrant<'_, T>> {
+        // SAFETY: `split` API ensures SPSC property
+        let (read, used) = unsafe { self.inner.readable() };
+
+        if used == 0 {
+            return None;
+        }
+
+        Some(ReadGrant {
+            inner: self.inner,
+            read,
+        })
+    }
+
+    /// Grants access to all the elements in the channel that are contiguous in memory, starting
+    /// from the oldest one, without moving them out of the channel
+    ///
+    /// Returns `None` if the channel is observed as being empty
+    pub fn peek_grant_slice(&mut self) -> Option<ReadSliceGrant<'_, T>> {
+        // SAFETY: `split` API ensures SPSC property
+        let (read, used) = unsafe { self.inner.readable() };
+
+        if used == 0 {
+            return None;
+        }
+
+        Some(ReadSliceGrant {
+            inner: self.inner,
+            read,
+            len: used,
+        })
+    }
+}
+
+impl<T> Drop for Receiver<'_, T> {
+    fn drop(&mut self) {
+        self.inner
+            .header
+            .closed
+            .store(true, atomic::Ordering::Release);
+
+        self.inner.wake_sender();
+    }
+}
+
+/// An iterator that receives data until the channel is observed as being empty
+///
+/// Created with `Receiver::try_iter`
+pub struct TryIter<'a, T> {
+    receiver: &'a Receiver<'a, T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// The error returned by `Sender::send_blocking` when the receiver is gone
+///
+/// Holds the value that could not be sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// The error returned by `Sender::send`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full
+    Full(T),
+    /// The receiver is gone
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Returns the value that could not be sent
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Disconnected(value) => value,
+        }
+    }
+}
+
+/// The error returned by `Receiver::recv_blocking` when the sender is gone and the channel has
+/// been drained
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+/// The error returned by `Receiver::recv`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty
+    Empty,
+    /// The channel is empty and the sender is gone
+    Disconnected,
+}
+
+/// Write access to a free slot of a channel
+///
+/// Created with `Sender::grant`. The slot is only sent once it has been initialized with
+/// `WriteGrant::write` or `WriteGrant::commit`; dropping the grant leaves the slot free
+///
+/// Unlike a read grant, which releases on drop, a write grant deliberately does not commit on
+/// drop: the grant cannot tell whether the slot was initialized through `DerefMut`, e.g. by a DMA
+/// transfer that failed halfway, and sending an uninitialized slot would let the receiver read
+/// it. That's also why `commit` is `unsafe`; `write` is the safe path
+pub struct WriteGrant<'a, T> {
+    inner: Inner<'a, T>,
+    write: usize,
+}
+
+impl<T> WriteGrant<'_, T> {
+    /// Initializes the slot with `value` and sends it
+    pub fn write(mut self, value: T) {
+        MaybeUninit::write(&mut self, value);
+
+        // SAFETY: slot was initialized above
+        unsafe { self.commit() }
+    }
+
+    /// Sends the slot
+    ///
+    /// # Safety
+    /// - The slot must have been initialized
+    pub unsafe fn commit(self) {
+        self.inner.commit(self.write, 1);
+    }
+}
+
+impl<T> ops::Deref for WriteGrant<'_, T> {
+    type Target = MaybeUninit<T>;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the grant mutably borrows the sender so only it can access the slot
+        unsafe { &*self.inner.slots(self.write, 1)[0].get() }
+    }
+}
+
+impl<T> ops::DerefMut for WriteGrant<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the grant mutably borrows the sender so only it can access the slot
+        unsafe { &mut *self.inner.slots(self.write, 1)[0].get() }
+    }
+}
+
+/// Write access to contiguous free slots of a channel
+///
+/// Created with `Sender::grant_slice`. Slots are only sent once they have been initialized and
+/// committed with `WriteSliceGrant::commit`; dropping the grant leaves the slots free, for the
+/// same reason as with `WriteGrant`
+pub struct WriteSliceGrant<'a, T> {
+    inner: Inner<'a, T>,
+    write: usize,
+    len: usize,
+}
+
+impl<T> WriteSliceGrant<'_, T> {
+    /// Sends the first `len` slots of the grant
+    ///
+    /// # Safety
+    /// - The first `len` slots must have been initialized
+    /// - `len` must not exceed the length of the grant
+    pub unsafe fn commit(self, len: usize) {
+        debug_assert!(len <= self.len);
+
+        self.inner.commit(self.write, len);
+    }
+}
+
+impl<T> ops::Deref for WriteSliceGrant<'_, T> {
+    type Target = [MaybeUninit<T>];
+
+    fn deref(&self) -> &Self::Target {
+        let slots = self.inner.slots(self.write, self.len);
+        // SAFETY: the grant mutably borrows the sender so only it can access the slots;
+        // `UnsafeCell` has the same layout as its contents
+        unsafe { slice::from_raw_parts(UnsafeCell::raw_get(slots.as_ptr()), slots.len()) }
+    }
+}
+
+impl<T> ops::DerefMut for WriteSliceGrant<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let slots = self.inner.slots(self.write, self.len);
+        // SAFETY: the grant mutably borrows the sender so only it can access the slots;
+        // `UnsafeCell` has the same layout as its contents
+        unsafe { slice::from_raw_parts_mut(UnsafeCell::raw_get(slots.as_ptr()), slots.len()) }
+    }
+}
+
+/// Access to the oldest element of a channel
+///
+/// Created with `Receiver::peek_grant`. When the grant is dropped, the element is dropped and its
+/// slot is released back to the sender
+pub struct ReadGrant<'a, T> {
+    inner: Inner<'a, T>,
+    read: usize,
+}
+
+impl<T> ops::Deref for ReadGrant<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the grant mutably borrows the receiver so only it can access the slot, which is
+        // initialized due to the state of the `write` cursor
+        unsafe { (*self.inner.slots(self.read, 1)[0].get()).assume_init_ref() }
+    }
+}
+
+impl<T> ops::DerefMut for ReadGrant<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: see `Deref` implementation
+        unsafe { (*self.inner.slots(self.read, 1)[0].get()).assume_init_mut() }
+    }
+}
+
+impl<T> Drop for ReadGrant<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the slot is initialized and released right after so it won't be read again
+        unsafe {
+            (*self.inner.slots(self.read, 1)[0].get()).assume_init_drop();
+        }
+
+        self.inner.release(self.read, 1);
+    }
+}
+
+/// Access to contiguous elements of a channel
+///
+/// Created with `Receiver::peek_grant_slice`. When the grant is dropped, all its elements are
+/// dropped and their slots are released back to the sender; use `ReadSliceGrant::release` to
+/// release only some of them
+pub struct ReadSliceGrant<'a, T> {
+    inner: Inner<'a, T>,
+    read: usize,
+    len: usize,
+}
+
+impl<T> ReadSliceGrant<'_, T> {
+    /// Drops the first `len` elements of the grant and releases their slots back to the sender
+    ///
+    /// The remaining elements stay in the channel
+    ///
+    /// # Panics
+    /// If `len` exceeds the length of the grant
+    pub fn release(mut self, len: usize) {
+        assert!(
+            len <= self.len,
+            "release length exceeds the length of the grant"
+        );
+
+        self.len = len;
+    }
+}
+
+impl<T> ops::Deref for ReadSliceGrant<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        let slots = self.inner.slots(self.read, self.len);
+        // SAFETY: the grant mutably borrows the receiver so only it can access the slots, which
+        // are initialized due to the state of the `write` cursor; `UnsafeCell` and
+        // `MaybeUninit` have the same layout as their contents
+        unsafe { slice::from_raw_parts(slots.as_ptr().cast::<T>(), slots.len()) }
+    }
+}
+
+impl<T> ops::DerefMut for ReadSliceGrant<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let slots = self.inner.slots(self.read, self.len);
+        // SAFETY: see `Deref` implementation
+        unsafe {
+            slice::from_raw_parts_mut(UnsafeCell::raw_get(slots.as_ptr()).cast(), slots.len())
+        }
+    }
+}
+
+impl<T> Drop for ReadSliceGrant<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the elements are initialized and released right after so they won't be read again
+        unsafe {
+            ptr::drop_in_place::<[T]>(&mut **self);
+        }
+
+        self.inner.release(self.read, self.len);
+    }
+}
+
+/// The state that the sender and receiver share: the header with the cursors and the slots,
+/// which may live elsewhere
+struct Inner<'a, T> {
+    header: &'a Header,
+    buf: &'a [UnsafeCell<MaybeUninit<T>>],
+    /// `None` when the channel lives in `shared` memory
+    wakers: Option<&'a Wakers>,
+}
+
+impl<T> Clone for Inner<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Inner<'_, T> {}
+
+/// The wakers that each side registers while it waits for the other side
+///
+/// Each side's waker is woken when the other side is dropped and, with the `async` feature, when
+/// the other side sends or receives data
+struct Wakers {
+    sender: AtomicWaker,
+    receiver: AtomicWaker,
+}
+
+impl Wakers {
+    const fn new() -> Self {
+        Self {
+            sender: AtomicWaker::new(),
+            receiver: AtomicWaker::new(),
+        }
+    }
+}
+
+/// The state that the sender and receiver share, apart from the slots
+///
+/// Its layout is part of the `shared` memory layout so it only holds position-independent data
+#[repr(C)]
+struct Header {
+    read: Cursor,
+    write: Cursor,
+    closed: AtomicBool,
+}
+
+impl Header {
+    const fn new() -> Self {
+        Self {
+            read: Cursor::new(),
+            write: Cursor::new(),
+            closed: AtomicBool::new(false),
+        }
+    }
+}
+
+/// A cursor owned by one side of the channel
+///
+/// Padded to a cache line so that the sender and receiver do not write to the same cache line
+/// when they update their cursors. Positions are 32-bit wide, whatever the width of `usize`, so
+/// that both sides of a `shared` channel agree on the layout
+#[repr(C, align(64))]
+struct Cursor {
+    position: AtomicU32,
+    /// The owner's local copy of the other side's cursor
+    ///
+    /// It lags behind the actual position of the other cursor so it's only reloaded when the
+    /// channel looks full (sender) or empty (receiver)
+    peer: Cell<u32>,
+}
+
+impl Cursor {
+    const fn new() -> Self {
+        Self {
+            position: AtomicU32::new(0),
+            peer: Cell::new(0),
+        }
+    }
+
+    fn load(&self, order: atomic::Ordering) -> usize {
+        self.position.load(order) as usize
+    }
+
+    // cursors stay below `u32::MAX`, see `Inner::advance`
+    fn store(&self, cursor: usize, order: atomic::Ordering) {
+        self.position.store(cursor as u32, order);
+    }
+
+    fn peer(&self) -> usize {
+        self.peer.get() as usize
+    }
+
+    fn set_peer(&self, cursor: usize) {
+        self.peer.set(cursor as u32);
+    }
+}
+
+impl<'a, T> Inner<'a, T> {
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn send(&self, value: T) -> Result<(), T> {
+        let current_write = self.header.write.load(atomic::Ordering::Relaxed);
+        let capacity = self.buf.len();
+
+        if self.distance(self.header.write.peer(), current_write) == capacity {
+            // Acquire: all operations AFTER the barrier cannot be reordered to BEFORE it
+            // this synchronizes with the Release `read` store in `recv` ensuring that
+            // the `slot` read in `recv` is completed before the `slot` write that happens below
+            let acquired_read = self.header.read.load(atomic::Ordering::Acquire);
+            self.header.write.set_peer(acquired_read);
+
+            let current_len = self.distance(acquired_read, current_write);
+            if current_len == capacity {
+                // full
+                return Err(value);
+            }
+        }
+
+        // SAFETY: SPSC, atomic fences and `if` condition ensure no data race with `recv` operation
+        unsafe {
+            self.slot_ptr(current_write).write(value);
+        }
+
+        self.commit(current_write, 1);
+
+        Ok(())
+    }
+
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn recv(&self) -> Option<T> {
+        let current_read = self.header.read.load(atomic::Ordering::Relaxed);
+
+        if current_read == self.header.read.peer() {
+            // Acquire: all operations AFTER the barrier cannot be reordered to BEFORE it
+            // this synchronizes with the Release `write` store in `send` ensuring that
+            // the `slot` write in `send` is completed before the `slot` read that happens below
+            let acquired_write = self.header.write.load(atomic::Ordering::Acquire);
+            self.header.read.set_peer(acquired_write);
+
+            if current_read == acquired_write {
+                // empty
+                return None;
+            }
+        }
+
+        // SAFETY: valid allocation; known to be initialized due to state of `write` cursor;
+        // SPSC, atomic fences and `if` condition ensure no data race with `send` operation
+        let value = unsafe { self.slot_ptr(current_read).read() };
+
+        // Release: operations that PRECEDE this barrier cannot be reordered to AFTER it
+        self.header
+            .read
+            .store(self.advance(current_read, 1), atomic::Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Returns the `write` cursor and the number of free slots that follow it without wrapping
+    /// around the end of `buf`
+    ///
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn writable(&self) -> (usize, usize) {
+        // SAFETY: caller ensures the SPSC property
+        let (current_write, free) = unsafe { self.free() };
+        let capacity = self.buf.len();
+
+        (
+            current_write,
+            free.min(capacity - self.index(current_write)),
+        )
+    }
+
+    /// Returns the `write` cursor and the number of free slots
+    ///
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn free(&self) -> (usize, usize) {
+        let current_write = self.header.write.load(atomic::Ordering::Relaxed);
+
+        // Acquire: synchronizes with the Release `read` store in `release`; see `send`
+        let acquired_read = self.header.read.load(atomic::Ordering::Acquire);
+        self.header.write.set_peer(acquired_read);
+
+        (
+            current_write,
+            self.buf.len() - self.distance(acquired_read, current_write),
+        )
+    }
+
+    /// Returns the `read` cursor and the number of initialized slots that follow it without
+    /// wrapping around the end of `buf`
+    ///
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn readable(&self) -> (usize, usize) {
+        // SAFETY: caller ensures the SPSC property
+        let (current_read, used) = unsafe { self.used() };
+        let capacity = self.buf.len();
+
+        (current_read, used.min(capacity - self.index(current_read)))
+    }
+
+    /// Returns the `read` cursor and the number of initialized slots
+    ///
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn used(&self) -> (usize, usize) {
+        let current_read = self.header.read.load(atomic::Ordering::Relaxed);
+
+        // Acquire: synchronizes with the Release `write` store in `commit`; see `recv`
+        let acquired_write = self.header.write.load(atomic::Ordering::Acquire);
+        self.header.read.set_peer(acquired_write);
+
+        (current_read, self.distance(current_read, acquired_write))
+    }
+
+    /// Returns the cursor that is `len` slots after `cursor`
+    ///
+    /// Cursors wrap around at a multiple of the capacity so that a cursor always refers to the
+    /// same slot; with a power of two capacity that's simply the whole range of `u32`
+    fn advance(&self, cursor: usize, len: usize) -> usize {
+        let capacity = self.buf.len();
+
+        if capacity.is_power_of_two() {
+            (cursor as u32).wrapping_add(len as u32) as usize
+        } else {
+            // cannot overflow as cursors stay below `wrap` which is at most `u32::MAX / 2`
+            let cursor = cursor + len;
+            let wrap = self.wrap();
+
+            if cursor >= wrap {
+                cursor - wrap
+            } else {
+                cursor
+            }
+        }
+    }
+
+    /// Returns the number of slots from cursor `from` to cursor `to`
+    fn distance(&self, from: usize, to: usize) -> usize {
+        if self.buf.len().is_power_of_two() {
+            (to as u32).wrapping_sub(from as u32) as usize
+        } else if to >= from {
+            to - from
+        } else {
+            to + self.wrap() - from
+        }
+    }
+
+    /// Returns the cursor value that wraps around to `0` when the capacity is not a power of two
+    fn wrap(&self) -> usize {
+        let capacity = self.buf.len();
+
+        capacity * (u32::MAX as usize / 2 / capacity)
+    }
+
+    /// Returns the index into `buf` that `cursor` refers to
+    fn index(&self, cursor: usize) -> usize {
+        let capacity = self.buf.len();
+
+        if capacity.is_power_of_two() {
+            cursor & (capacity - 1)
+        } else {
+            cursor % capacity
+        }
+    }
+
+    /// Returns a pointer to the slot that `cursor` refers to
+    fn slot_ptr(&self, cursor: usize) -> *mut T {
+        // SAFETY: within bounds due to modulo operation
+        let slot = unsafe { self.buf.get_unchecked(self.index(cursor)) };
+
+        slot.get().cast()
+    }
+
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn send_iter<I>(&self, iter: &mut I) -> usize
+    where
+        I: Iterator<Item = T>,
+    {
+        // SAFETY: caller ensures the SPSC property
+        let (current_write, free) = unsafe { self.free() };
+
+        let mut len = 0;
+        while len < free {
+            let Some(value) = iter.next() else {
+                break;
+            };
+
+            // SAFETY: SPSC, atomic fences and the number of free slots ensure no data race with
+            // the receiver
+            unsafe {
+                self.slot_ptr(self.advance(current_write, len)).write(value);
+            }
+            len += 1;
+        }
+
+        self.commit(current_write, len);
+
+        len
+    }
+
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    unsafe fn send_slice(&self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        // SAFETY: caller ensures the SPSC property
+        let (current_write, free) = unsafe { self.free() };
+        let capacity = self.buf.len();
+
+        let len = values.len().min(free);
+        // the elements that fit before the end of `buf`; the rest wrap around to its start
+        let head = len.min(capacity - self.index(current_write));
+        let (head_values, tail_values) = values[..len].split_at(head);
+
+        // SAFETY: SPSC, atomic fences and the number of free slots ensure no data race with
+        // the receiver; `head` ensures the copies stay within `buf`
+        unsafe {
+            ptr::copy_nonoverlapping(
+                head_values.as_ptr(),
+                self.slot_ptr(current_write),
+                head_values.len(),
+            );
+            ptr::copy_nonoverlapping(
+                tail_values.as_ptr(),
+                self.slot_ptr(self.advance(current_write, head)),
+                tail_values.len(),
+            );
+        }
+
+        self.commit(current_write, len);
+
+        len
+    }
+
+    /// Moves up to `max` elements out of the channel and passes them to `f`
+    ///
+    /// # Safety
+    /// - Caller must ensure that the SPSC property holds
+    /// - `f` must not access the channel
+    unsafe fn recv_with<F>(&self, max: usize, mut f: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        // SAFETY: caller ensures the SPSC property
+        let (current_read, used) = unsafe { self.used() };
+
+        // releases the slots that have been moved out, even if `f` panics
+        let mut guard = ReleaseGuard {
+            inner: *self,
+            read: current_read,
+            len: 0,
+        };
+
+        let len = used.min(max);
+        while guard.len < len {
+            // SAFETY: known to be initialized due to state of `write` cursor; SPSC and atomic
+            // fences ensure no data race with the sender
+            let value = unsafe { self.slot_ptr(self.advance(current_read, guard.len)).read() };
+            guard.len += 1;
+
+            f(value);
+        }
+
+        len
+    }
+
+    /// Returns the `len` slots that start at `cursor`
+    ///
+    /// The range must not wrap around the end of `buf`
+    fn slots(&self, cursor: usize, len: usize) -> &'a [UnsafeCell<MaybeUninit<T>>] {
+        &self.buf[self.index(cursor)..][..len]
+    }
+
+    /// Makes the `len` slots that follow the `write` cursor available to the receiver
+    fn commit(&self, current_write: usize, len: usize) {
+        // Release: the slot writes that PRECEDE this barrier cannot be reordered to AFTER it
+        self.header
+            .write
+            .store(self.advance(current_write, len), atomic::Ordering::Release);
+
+        #[cfg(feature = "async")]
+        self.wake_receiver();
+    }
+
+    /// Wakes the receiver's task or blocked thread, if it registered one
+    fn wake_receiver(&self) {
+        if let Some(wakers) = self.wakers {
+            wakers.receiver.wake();
+        }
+    }
+
+    /// Wakes the sender's task or blocked thread, if it registered one
+    fn wake_sender(&self) {
+        if let Some(wakers) = self.wakers {
+            wakers.sender.wake();
+        }
+    }
+
+    /// Makes the `len` slots that follow the `read` cursor available to the sender
+    fn release(&self, current_read: usize, len: usize) {
+        // Release: the slot reads that PRECEDE this barrier cannot be reordered to AFTER it
+        self.header
+            .read
+            .store(self.advance(current_read, len), atomic::Ordering::Release);
+
+        #[cfg(feature = "async")]
+        self.wake_sender();
+    }
+
+    /// Drops the items that have been sent but not received
+    ///
+    /// Both sides of the channel must be gone
+    fn drop_items(&self) {
+        let write = self.header.write.load(atomic::Ordering::Relaxed);
+        let mut read = self.header.read.load(atomic::Ordering::Relaxed);
+
+        while read != write {
+            // SAFETY: slots between the `read` and `write` cursors are initialized
+            unsafe {
+                self.slot_ptr(read).drop_in_place();
+            }
+            read = self.advance(read, 1);
+        }
+
+        self.header.read.store(read, atomic::Ordering::Relaxed);
+    }
+}
+
+struct ReleaseGuard<'a, T> {
+    inner: Inner<'a, T>,
+    read: usize,
+    len: usize,
+}
+
+impl<T> Drop for ReleaseGuard<'_, T> {
+    fn drop(&mut self) {
+        self.inner.release(self.read, self.len);
+    }
+}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Sender<'_, T> where T: Send {}
+
+// SAFETY: allowing the handle to move to another thread, allows sending values to another thread;
+// therefore the value must be Send as well
+unsafe impl<T> Send for Receiver<'_, T> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_one_works() {
+        let channel = Box::leak(Box::new(Channel::<i32, 1>::new()));
+        let (sender, receiver) = channel.split();
+
+        let value = 42;
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        assert_eq!(Ok(()), sender.send(value));
+        assert_eq!(Err(TrySendError::Full(value)), sender.send(value));
+        assert_eq!(Ok(value), receiver.recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn fifo_order() {
+        let channel = Box::leak(Box::new(Channel::<i32, 2>::new()));
+        let (sender, receiver) = channel.split();
+
+        let value1 = 42;
+        let value2 = 24;
+        assert_eq!(Ok(()), sender.send(value1));
+        assert_eq!(Ok(()), sender.send(value2));
+
+        assert_eq!(Ok(value1), receiver.recv());
+        assert_eq!(Ok(value2), receiver.recv());
+    }
+
+    #[test]
+    fn works_with_non_power_of_two() {
+        let channel = Box::leak(Box::new(Channel::<i32, 3>::new()));
+        let (sender, receiver) = channel.split();
+
+        let value1 = 42;
+        let value2 = 24;
+        let value3 = 123;
+
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+
+        assert_eq!(Ok(()), sender.send(value1));
+        assert_eq!(Ok(()), sender.send(value2));
+        assert_eq!(Ok(()), sender.send(value3));
+        assert_eq!(Err(TrySendError::Full(value3)), sender.send(value3));
+
+        assert_eq!(Ok(value1), receiver.recv());
+        assert_eq!(Ok(value2), receiver.recv());
+        assert_eq!(Ok(value3), receiver.recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn cursor_wrap_around() {
+        let channel = Box::leak(Box::new(Channel::<i32, 2>::new()));
+        set_cursors(channel, u32::MAX as usize);
+        let (sender, receiver) = channel.split();
+
+        let value1 = 42;
+        let value2 = 24;
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        assert_eq!(Ok(()), sender.send(value1));
+        assert_eq!(Ok(()), sender.send(value2));
+        assert_eq!(Err(TrySendError::Full(value1)), sender.send(value1));
+        assert_eq!(Ok(value1), receiver.recv());
+        assert_eq!(Ok(value2), receiver.recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn non_power_of_two_cursor_wrap_around() {
+        let mut channel = Channel::<i32, 3>::new();
+        let wrap = channel.inner().wrap();
+        set_cursors(&mut channel, wrap - 2);
+        let (sender, receiver) = channel.split();
+
+        for value in 0..3 {
+            assert_eq!(Ok(()), sender.send(value));
+        }
+        assert_eq!(Err(TrySendError::Full(3)), sender.send(3));
+
+        for value in 0..3 {
+            assert_eq!(Ok(value), receiver.recv());
+            assert_eq!(Ok(()), sender.send(value + 3));
+        }
+        for value in 3..6 {
+            assert_eq!(Ok(value), receiver.recv());
+        }
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn storage_channel() {
+        let mut storage = [MaybeUninit::<u8>::uninit(); 64];
+        // e.g. read from a configuration block
+        let len = 23;
+        let mut channel = StorageChannel::<u32, _>::new(&mut storage[..len]);
+        // 3 bytes are kept to realign the slots
+        let capacity = channel.capacity();
+        assert_eq!(5, capacity);
+
+        let (sender, receiver) = channel.split();
+        for value in 0..capacity as u32 {
+            assert_eq!(Ok(()), sender.send(value));
+        }
+        assert_eq!(Err(TrySendError::Full(42)), sender.send(42));
+        for value in 0..capacity as u32 {
+            assert_eq!(Ok(value), receiver.recv());
+        }
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn storage_channel_survives_realignment() {
+        #[derive(Debug, PartialEq)]
+        #[repr(align(128))]
+        struct Aligned(u32);
+
+        #[repr(C, align(128))]
+        struct At0<C> {
+            channel: C,
+        }
+
+        #[repr(C, align(128))]
+        struct At64<C> {
+            padding: [u8; 64],
+            channel: C,
+        }
+
+        let storage = [MaybeUninit::<u8>::uninit(); 128 * 3 + 127];
+        let mut at0 = At0 {
+            channel: StorageChannel::<Aligned, _>::new(storage),
+        };
+        {
+            let (sender, _receiver) = at0.channel.split();
+            assert_eq!(Ok(()), sender.send(Aligned(1)));
+            assert_eq!(Ok(()), sender.send(Aligned(2)));
+        }
+
+        let offset = at0.channel.offset;
+        let mut at64 = At64 {
+            padding: [0; 64],
+            channel: at0.channel,
+        };
+        let (sender, receiver) = at64.channel.split();
+        assert_eq!(Ok(Aligned(1)), receiver.recv());
+        assert_eq!(Ok(()), sender.send(Aligned(3)));
+        assert_eq!(Ok(Aligned(2)), receiver.recv());
+        assert_eq!(Ok(Aligned(3)), receiver.recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        drop((sender, receiver));
+        assert_ne!(offset, at64.channel.offset);
+        assert_eq!(0, at64.padding[0]);
+    }
+
+    #[test]
+    fn scoped_split() {
+        let mut channel = Channel::<i32, 4>::new();
+        let (sender, receiver) = channel.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for value in 0..100 {
+                    while sender.send(value).is_err() {}
+                }
+            });
+
+            for expected in 0..100 {
+                loop {
+                    if let Ok(value) = receiver.recv() {
+                        assert_eq!(expected, value);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn dropping_the_channel_drops_pending_items() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut channel = Channel::<_, 2>::new();
+        set_cursors(&mut channel, u32::MAX as usize);
+
+        let (sender, receiver) = channel.split();
+        assert!(sender.send(value.clone()).is_ok());
+        assert!(sender.send(value.clone()).is_ok());
+        assert!(receiver.recv().is_ok());
+        assert!(sender.send(value.clone()).is_ok());
+        assert_eq!(3, Rc::strong_count(&value));
+
+        drop((sender, receiver));
+        drop(channel);
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn sender_drop_disconnects() {
+        let mut channel = Channel::<i32, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        let value = 42;
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        assert_eq!(Ok(()), sender.send(value));
+        assert!(!receiver.is_closed());
+
+        drop(sender);
+
+        assert!(receiver.is_closed());
+        assert_eq!(Ok(value), receiver.recv());
+        assert_eq!(Err(TryRecvError::Disconnected), receiver.recv());
+    }
+
+    #[test]
+    fn receiver_drop_disconnects() {
+        let mut channel = Channel::<i32, 1>::new();
+        let (sender, receiver) = channel.split();
+
+        let value = 42;
+        assert_eq!(Ok(()), sender.send(value));
+        assert_eq!(Err(TrySendError::Full(value)), sender.send(value));
+        assert!(!sender.is_closed());
+
+        drop(receiver);
+
+        assert!(sender.is_closed());
+        assert_eq!(Err(TrySendError::Disconnected(value)), sender.send(value));
+        assert_eq!(
+            Err(SendError(value)),
+            sender.send_blocking(value, &mut || unreachable!())
+        );
+    }
+
+    #[test]
+    fn split_again_reopens() {
+        let mut channel = Channel::<i32, 1>::new();
+        let (sender, receiver) = channel.split();
+        drop((sender, receiver));
+
+        let (sender, receiver) = channel.split();
+        assert!(!sender.is_closed());
+        assert!(!receiver.is_closed());
+    }
+
+    #[test]
+    fn grants() {
+        let mut channel = Channel::<i32, 2>::new();
+        let (mut sender, mut receiver) = channel.split();
+
+        assert!(receiver.peek_grant().is_none());
+
+        let value = 42;
+        sender.grant().unwrap().write(value);
+
+        // dropping a write grant does not send anything
+        assert!(sender.grant().is_some());
+
+        let mut grant = receiver.peek_grant().unwrap();
+        assert_eq!(value, *grant);
+        *grant += 1;
+        drop(grant);
+
+        assert!(receiver.peek_grant().is_none());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+    }
+
+    #[test]
+    fn slice_grants_are_contiguous() {
+        let mut channel = Channel::<u8, 4>::new();
+        let (mut sender, mut receiver) = channel.split();
+
+        assert_eq!(Ok(()), sender.send(0));
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Ok(()), sender.send(2));
+        assert_eq!(Ok(0), receiver.recv());
+        assert_eq!(Ok(1), receiver.recv());
+
+        // only the slot at the end of the buffer
+        let mut grant = sender.grant_slice().unwrap();
+        assert_eq!(1, grant.len());
+        grant[0].write(3);
+        // SAFETY: initialized the only slot
+        unsafe { grant.commit(1) }
+
+        // wrapped around
+        let mut grant = sender.grant_slice().unwrap();
+        assert_eq!(2, grant.len());
+        grant[0].write(4);
+        // SAFETY: initialized the first slot
+        unsafe { grant.commit(1) }
+
+        let grant = receiver.peek_grant_slice().unwrap();
+        assert_eq!([2, 3], *grant);
+        grant.release(1);
+
+        let grant = receiver.peek_grant_slice().unwrap();
+        assert_eq!([3], *grant);
+        drop(grant);
+
+        let grant = receiver.peek_grant_slice().unwrap();
+        assert_eq!([4], *grant);
+        drop(grant);
+
+        assert!(receiver.peek_grant_slice().is_none());
+    }
+
+    #[test]
+    fn read_grant_drops_the_element() {
+        use std::rc::Rc;
+
+        let value = Rc::new(42);
+        let mut channel = Channel::<_, 2>::new();
+        let (sender, mut receiver) = channel.split();
+
+        assert!(sender.send(value.clone()).is_ok());
+        assert_eq!(2, Rc::strong_count(&value));
+
+        let grant = receiver.peek_grant().unwrap();
+        assert_eq!(42, **grant);
+        drop(grant);
+
+        assert_eq!(1, Rc::strong_count(&value));
+    }
+
+    #[test]
+    fn batches_wrap_around() {
+        let mut channel = Channel::<i32, 4>::new();
+        set_cursors(&mut channel, u32::MAX as usize - 1);
+        let (mut sender, mut receiver) = channel.split();
+
+        assert_eq!(Ok(()), sender.send(0));
+        assert_eq!(3, sender.send_slice(&[1, 2, 3, 4]));
+        assert_eq!(0, sender.send_slice(&[4]));
+
+        let mut buf = [0; 3];
+        assert_eq!(3, receiver.recv_into(&mut buf));
+        assert_eq!([0, 1, 2], buf);
+
+        let mut iter = 4..;
+        assert_eq!(3, sender.send_iter(iter.by_ref()));
+        assert_eq!(Some(7), iter.next());
+
+        let mut received = std::vec::Vec::new();
+        assert_eq!(4, receiver.drain(|value| received.push(value)));
+        assert_eq!([3, 4, 5, 6], *received);
+
+        assert_eq!(0, receiver.recv_into(&mut buf));
+        assert_eq!(0, receiver.drain(|_| unreachable!()));
+    }
+
+    #[test]
+    fn iterator_adapters() {
+        let mut channel = Channel::<i32, 4>::new();
+        let (mut sender, mut receiver) = channel.split();
+
+        assert_eq!(Ok(()), sender.try_extend([1, 2]));
+        assert_eq!([1, 2], *receiver.try_iter().collect::<std::vec::Vec<_>>());
+        assert_eq!(None, receiver.try_iter().next());
+
+        let mut values = 3..10;
+        assert_eq!(
+            Err(TrySendError::Full(7)),
+            sender.try_extend(values.by_ref())
+        );
+        assert_eq!(Some(8), values.next());
+
+        #[repr(align(4))]
+        struct Align4<T>(T);
+
+        let mut storage = Align4([MaybeUninit::<u8>::uninit(); 12]);
+        let mut vec = Vec::<i32, _>::new(&mut storage.0);
+        assert_eq!(3, vec.capacity());
+        assert_eq!(Ok(()), vec.push(0));
+        assert_eq!(2, receiver.drain_to(&mut vec));
+        assert_eq!([0, 3, 4], *vec);
+        assert_eq!(0, receiver.drain_to(&mut vec));
+
+        assert_eq!(
+            [50, 60],
+            *receiver
+                .try_iter()
+                .map(|value| value * 10)
+                .collect::<std::vec::Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn introspection() {
+        let mut channel = Channel::<i32, 3>::new();
+        let (sender, mut receiver) = channel.split();
+
+        assert_eq!(3, sender.capacity());
+        assert_eq!(3, receiver.capacity());
+
+        assert!(sender.is_empty());
+        assert!(receiver.is_empty());
+        assert_eq!(None, receiver.peek());
+
+        assert_eq!(Ok(()), sender.send(1));
+        assert_eq!(Ok(()), sender.send(2));
+
+        assert_eq!(2, sender.len());
+        assert_eq!(2, receiver.len());
+        assert_eq!(1, sender.free());
+        assert_eq!(1, receiver.free());
+
+        assert_eq!(Ok(()), sender.send(3));
+        assert!(sender.is_full());
+        assert!(receiver.is_full());
+
+        assert_eq!(Some(&1), receiver.peek());
+        *receiver.peek_mut().unwrap() = 4;
+        assert_eq!(Ok(4), receiver.recv());
+        assert_eq!(Some(&2), receiver.peek());
+
+        assert!(!sender.is_full());
+        assert_eq!(2, receiver.len());
+    }
+
+    #[test]
+    fn blocking() {
+        const MESSAGES: usize = 10_000;
+
+        let mut channel = Channel::<usize, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                let mut wait = std::thread::yield_now;
+                for value in 0..MESSAGES {
+                    assert_eq!(Ok(()), sender.send_blocking(value, &mut wait));
+                }
+            });
+
+            let mut wait = std::thread::yield_now;
+            for value in 0..MESSAGES {
+                assert_eq!(Ok(value), receiver.recv_blocking(&mut wait));
+            }
+            assert_eq!(Err(RecvError), receiver.recv_blocking(&mut wait));
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn blocking_with_park() {
+        use crate::wait::Park;
+
+        const MESSAGES: usize = 1_000;
+
+        let mut channel = Channel::<usize, 2>::new();
+        let (sender, receiver) = channel.split();
+        let receiver_thread = std::thread::current();
+
+        std::thread::scope(|s| {
+            let sender_thread = s
+                .spawn(move || {
+                    let mut wait = Park::new(receiver_thread);
+
+                    for value in 0..MESSAGES {
+                        assert_eq!(Ok(()), sender.send_blocking(value, &mut wait));
+                    }
+                })
+                .thread()
+                .clone();
+
+            let mut wait = Park::new(sender_thread);
+            for value in 0..MESSAGES {
+                assert_eq!(Ok(value), receiver.recv_blocking(&mut wait));
+            }
+            assert_eq!(Err(RecvError), receiver.recv_blocking(&mut wait));
+        });
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn dropping_the_receiver_unparks_a_blocked_sender() {
+        use crate::wait::Park;
+
+        let mut channel = Channel::<i32, 1>::new();
+        let (sender, receiver) = channel.split();
+        assert_eq!(Ok(()), sender.send(0));
+
+        std::thread::scope(|s| {
+            let sender = s.spawn(move || {
+                let mut wait = Park::new(std::thread::current());
+                sender.send_blocking(1, &mut wait)
+            });
+
+            // lets the sender park on the full channel
+            for _ in 0..10 {
+                std::thread::yield_now();
+            }
+            drop(receiver);
+
+            assert_eq!(Err(SendError(1)), sender.join().unwrap());
+        });
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut channel = Channel::<i32, 1>::new();
+        let (sender, receiver) = channel.split();
+
+        let waits = Cell::new(0);
+        let mut wait = || waits.set(waits.get() + 1);
+        let after_two_waits = || waits.get() == 2;
+
+        assert_eq!(
+            Err(TryRecvError::Empty),
+            receiver.recv_timeout(after_two_waits, &mut wait)
+        );
+        assert_eq!(2, waits.get());
+
+        waits.set(0);
+        assert_eq!(Ok(()), sender.send_timeout(1, after_two_waits, &mut wait));
+        assert_eq!(
+            Err(TrySendError::Full(2)),
+            sender.send_timeout(2, after_two_waits, &mut wait)
+        );
+        assert_eq!(2, waits.get());
+
+        assert_eq!(Ok(1), receiver.recv_timeout(after_two_waits, &mut wait));
+        drop(sender);
+        assert_eq!(
+            Err(TryRecvError::Disconnected),
+            receiver.recv_timeout(|| false, &mut wait)
+        );
+    }
+
+    #[test]
+    fn check_sender_is_send() {
+        is_send::<Sender<i32>>();
+    }
+
+    #[test]
+    fn check_receiver_is_send() {
+        is_send::<Receiver<i32>>();
+    }
+
+    fn set_cursors<T, const N: usize>(channel: &mut Channel<T, N>, position: usize) {
+        for cursor in [&mut channel.header.read, &mut channel.header.write] {
+            cursor.store(position, atomic::Ordering::Relaxed);
+            cursor.set_peer(position);
+        }
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
@@ -0,0 +1,292 @@
+//! A single-producer, single-consumer (SPSC) channel with priority lanes
+//!
+//! Each of the `K` lanes is an SPSC channel. The receiver takes data from the lane with the
+//! highest priority that has some, so urgent messages jump ahead of bulk data. Lane `0` has the
+//! highest priority; data stays in FIFO order within a lane
+//!
+//! `Channel` holds `K` lanes of the same capacity. Lanes of different capacities, or of different
+//! channel types, can be combined with `Sender::from_lanes` and `Receiver::from_lanes`
+
+use super::{Inner, TryRecvError, TrySendError};
+
+/// A fixed-capacity, single-producer, single-consumer (SPSC) channel with `K` priority lanes of
+/// capacity `N` each
+pub struct Channel<T, const N: usize, const K: usize> {
+    lanes: [super::Channel<T, N>; K],
+}
+
+impl<T, const N: usize, const K: usize> Channel<T, N, K> {
+    /// Creates a new channel
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(K > 0, "there must be at least one lane");
+        }
+
+        Self {
+            lanes: [const { super::Channel::new() }; K],
+        }
+    }
+
+    /// Splits this channel into sender and receiver parts
+    ///
+    /// The channel is mutably borrowed for as long as either part is live
+    pub fn split(&mut self) -> (Sender<'_, T, K>, Receiver<'_, T, K>) {
+        for lane in &mut self.lanes {
+            // the parts of a previous split may have closed the channel
+            *lane.header.closed.get_mut() = false;
+        }
+        let inners: [Inner<'_, T>; K] = self.lanes.each_ref().map(super::Channel::inner);
+
+        (
+            Sender {
+                lanes: inners.map(|inner| super::Sender { inner }),
+            },
+            Receiver {
+                lanes: inners.map(|inner| super::Receiver { inner }),
+            },
+        )
+    }
+}
+
+/// The sender side of a channel
+pub struct Sender<'a, T, const K: usize> {
+    lanes: [super::Sender<'a, T>; K],
+}
+
+impl<'a, T, const K: usize> Sender<'a, T, K> {
+    /// Combines the senders of `K` channels, which may have different capacities, into the sender
+    /// of a priority channel
+    ///
+    /// `lanes[0]` has the highest priority. The receivers of those channels should be combined,
+    /// in the same order, with `Receiver::from_lanes`
+    pub fn from_lanes(lanes: [super::Sender<'a, T>; K]) -> Self {
+        const {
+            assert!(K > 0, "there must be at least one lane");
+        }
+
+        Self { lanes }
+    }
+
+    /// Sends data through the lane of priority `prio`
+    ///
+    /// Returns `Full` if that lane is observed as being full and `Disconnected` if the receiver
+    /// is gone
+    ///
+    /// # Panics
+    /// If `prio` is not a lane, i.e. it's `K` or larger
+    pub fn send(&self, prio: usize, value: T) -> Result<(), TrySendError<T>> {
+        self.lanes[prio].send(value)
+    }
+
+    /// Returns `true` if the receiver, of any lane, has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.lanes.iter().any(super::Sender::is_closed)
+    }
+
+    /// Returns the total number of elements the lane of priority `prio` can hold
+    ///
+    /// # Panics
+    /// If `prio` is not a lane, i.e. it's `K` or larger
+    pub fn capacity(&self, prio: usize) -> usize {
+        self.lanes[prio].capacity()
+    }
+}
+
+/// The receiver side of a channel
+pub struct Receiver<'a, T, const K: usize> {
+    lanes: [super::Receiver<'a, T>; K],
+}
+
+impl<'a, T, const K: usize> Receiver<'a, T, K> {
+    /// Combines the receivers of `K` channels, which may have different capacities, into the
+    /// receiver of a priority channel
+    ///
+    /// `lanes[0]` has the highest priority
+    pub fn from_lanes(lanes: [super::Receiver<'a, T>; K]) -> Self {
+        const {
+            assert!(K > 0, "there must be at least one lane");
+        }
+
+        Self { lanes }
+    }
+
+    /// Receives data from the lane with the highest priority that has some
+    ///
+    /// Returns `Empty` if all the lanes are observed as being empty and `Disconnected` once the
+    /// sender is gone and all the lanes have been drained
+    pub fn recv(&self) -> Result<T, TryRecvError> {
+        self.recv_with_priority().map(|(_, value)| value)
+    }
+
+    /// Like `recv` but also returns the priority of the lane the data came from
+    pub fn recv_with_priority(&self) -> Result<(usize, T), TryRecvError> {
+        let mut disconnected = true;
+
+        for (prio, lane) in self.lanes.iter().enumerate() {
+            match lane.recv() {
+                Ok(value) => return Ok((prio, value)),
+                Err(TryRecvError::Empty) => disconnected = false,
+                Err(TryRecvError::Disconnected) => {}
+            }
+        }
+
+        Err(if disconnected {
+            TryRecvError::Disconnected
+        } else {
+            TryRecvError::Empty
+        })
+    }
+
+    /// Returns `true` if the sender, of every lane, has been dropped
+    ///
+    /// There may still be data left in the channel
+    pub fn is_closed(&self) -> bool {
+        self.lanes.iter().all(super::Receiver::is_closed)
+    }
+
+    /// Returns the total number of elements the lane of priority `prio` can hold
+    ///
+    /// # Panics
+    /// If `prio` is not a lane, i.e. it's `K` or larger
+    pub fn capacity(&self, prio: usize) -> usize {
+        self.lanes[prio].capacity()
+    }
+
+    /// Returns the number of elements in all the lanes as observed by the receiver
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(super::Receiver::len).sum()
+    }
+
+    /// Returns `true` if all the lanes are observed as being empty
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(super::Receiver::is_empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spsc;
+
+    #[test]
+    fn higher_priority_jumps_ahead() {
+        let mut channel = Channel::<i32, 4, 3>::new();
+        let (sender, receiver) = channel.split();
+
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        for value in 0..4 {
+            assert_eq!(Ok(()), sender.send(2, value));
+        }
+        assert_eq!(Err(TrySendError::Full(4)), sender.send(2, 4));
+        assert_eq!(Ok(()), sender.send(1, 10));
+
+        assert_eq!(Ok(10), receiver.recv());
+        assert_eq!(Ok(0), receiver.recv());
+        assert_eq!(Ok(()), sender.send(0, 20));
+        assert_eq!(Ok(()), sender.send(0, 21));
+        assert_eq!(3 + 2, receiver.len());
+
+        assert_eq!(Ok((0, 20)), receiver.recv_with_priority());
+        assert_eq!(Ok((0, 21)), receiver.recv_with_priority());
+        assert_eq!(Ok((2, 1)), receiver.recv_with_priority());
+        assert_eq!(Ok(2), receiver.recv());
+        assert_eq!(Ok(3), receiver.recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.recv());
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn lanes_of_different_capacities() {
+        let mut urgent = spsc::Channel::<i32, 1>::new();
+        let mut bulk = spsc::Channel::<i32, 4>::new();
+        let (urgent_sender, urgent_receiver) = urgent.split();
+        let (bulk_sender, bulk_receiver) = bulk.split();
+        let sender = Sender::from_lanes([urgent_sender, bulk_sender]);
+        let receiver = Receiver::from_lanes([urgent_receiver, bulk_receiver]);
+
+        assert_eq!(1, sender.capacity(0));
+        assert_eq!(4, receiver.capacity(1));
+
+        assert_eq!(Ok(()), sender.send(0, 1));
+        assert_eq!(Err(TrySendError::Full(2)), sender.send(0, 2));
+        for value in 10..14 {
+            assert_eq!(Ok(()), sender.send(1, value));
+        }
+
+        assert_eq!(Ok((0, 1)), receiver.recv_with_priority());
+        assert_eq!(Ok((1, 10)), receiver.recv_with_priority());
+
+        drop(sender);
+        assert!(receiver.is_closed());
+        assert_eq!(3, receiver.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn send_panics_on_invalid_priority() {
+        let mut channel = Channel::<i32, 4, 2>::new();
+        let (sender, _receiver) = channel.split();
+
+        let _ = sender.send(2, 0);
+    }
+
+    #[test]
+    fn drops_close_all_lanes() {
+        let mut channel = Channel::<i32, 4, 2>::new();
+
+        {
+            let (sender, receiver) = channel.split();
+            assert!(!receiver.is_closed());
+            drop(sender);
+            assert!(receiver.is_closed());
+            assert_eq!(Err(TryRecvError::Disconnected), receiver.recv());
+        }
+
+        let (sender, receiver) = channel.split();
+        assert!(!sender.is_closed());
+        drop(receiver);
+        assert!(sender.is_closed());
+    }
+
+    #[test]
+    fn lanes_stay_fifo_across_threads() {
+        const MESSAGES: usize = 10_000;
+
+        let mut channel = Channel::<usize, 3, 2>::new();
+        let (sender, receiver) = channel.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for value in 0..MESSAGES {
+                    while sender.send(value % 2, value).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+
+            let mut expected = [0, 1];
+            while expected != [MESSAGES, MESSAGES + 1] {
+                match receiver.recv_with_priority() {
+                    Ok((prio, value)) => {
+                        assert_eq!(expected[prio], value);
+                        expected[prio] += 2;
+                    }
+                    Err(_) => std::thread::yield_now(),
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn check_sender_and_receiver_are_send() {
+        is_send::<Sender<i32, 2>>();
+        is_send::<Receiver<i32, 2>>();
+    }
+
+    fn is_send<T>()
+    where
+        T: Send,
+    {
+    }
+}
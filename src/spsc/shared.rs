@@ -0,0 +1,340 @@
+//! Channels in memory that is shared between processes or cores
+//!
+//! One side initializes the memory region with `init`; then each side attaches to it, with
+//! `attach_sender` or `attach_receiver`, at whatever address the region is mapped. The region has
+//! the following layout, in native endianness, whatever the width of `usize` on each side
+//!
+//! | offset | field                                                              |
+//! |--------|--------------------------------------------------------------------|
+//! | 0      | magic (`u32`): `MAGIC`                                             |
+//! | 4      | version (`u32`): `VERSION`                                         |
+//! | 8      | element size (`u32`)                                               |
+//! | 12     | element alignment (`u32`)                                          |
+//! | 16     | capacity (`u32`)                                                   |
+//! | 64     | read cursor (`u32`), then the receiver's copy of the write cursor  |
+//! | 128    | write cursor (`u32`), then the sender's copy of the read cursor    |
+//! | 192    | closed flag (`u8`)                                                 |
+//! | 256    | slots, rounded up to the element alignment                         |
+//!
+//! Cursors are positions in the ring, not addresses, so they are valid in every address space
+
+use core::ptr::NonNull;
+use core::sync::atomic::{self, AtomicU32};
+use core::{mem, slice};
+
+use super::{Header, Inner, Receiver, Sender};
+
+/// The first field of an initialized region: "FIKA" in ASCII
+pub const MAGIC: u32 = u32::from_be_bytes(*b"FIKA");
+
+/// The version of the region layout
+pub const VERSION: u32 = 1;
+
+/// The alignment that a region must have
+pub const ALIGN: usize = mem::align_of::<SharedHeader>();
+
+#[repr(C)]
+struct SharedHeader {
+    magic: AtomicU32,
+    version: u32,
+    element_size: u32,
+    element_align: u32,
+    capacity: u32,
+    header: Header,
+}
+
+/// Returns the size, in bytes, of a region that holds `capacity` elements of type `T`
+pub const fn size_for<T>(capacity: usize) -> usize {
+    slots_offset::<T>() + capacity * mem::size_of::<T>()
+}
+
+/// Initializes a channel in the region of `len` bytes at `ptr` and returns its capacity
+///
+/// # Safety
+/// - `ptr` must be valid for writes of `len` bytes and aligned to `ALIGN`
+/// - No sender or receiver must be attached to the region
+///
+/// # Panics
+/// If the region cannot hold a single element
+pub unsafe fn init<T>(ptr: NonNull<u8>, len: usize) -> usize {
+    const {
+        assert!(
+            0 != mem::size_of::<T>(),
+            "zero-sized types are currently not supported"
+        );
+    }
+
+    let capacity = len.saturating_sub(slots_offset::<T>()) / mem::size_of::<T>();
+    let capacity = capacity.min(super::MAX_CAPACITY);
+    assert!(capacity > 0, "region cannot hold a single element");
+
+    let header = ptr.cast::<SharedHeader>().as_ptr();
+    // SAFETY: the caller ensures `header` is valid for writes and aligned
+    unsafe {
+        header.write(SharedHeader {
+            magic: AtomicU32::new(0),
+            version: VERSION,
+            element_size: mem::size_of::<T>() as u32,
+            element_align: mem::align_of::<T>() as u32,
+            capacity: capacity as u32,
+            header: Header::new(),
+        });
+    }
+
+    // Release: the header writes above happen before a side that observes the magic reads them
+    // SAFETY: the header was initialized above
+    unsafe {
+        (*header).magic.store(MAGIC, atomic::Ordering::Release);
+    }
+
+    capacity
+}
+
+/// Attaches the sender side to the channel in the region of `len` bytes at `ptr`
+///
+/// # Safety
+/// - `ptr` must point to a region of `len` bytes that is valid for reads and writes for `'a`,
+///   that `init` may have initialized for elements of type `T` and that no other sender is
+///   attached to
+/// - Values of type `T` must remain valid when they are moved to the receiver's side, e.g. they
+///   must not hold pointers into the sender's address space
+pub unsafe fn attach_sender<'a, T>(
+    ptr: NonNull<u8>,
+    len: usize,
+) -> Result<Sender<'a, T>, AttachError> {
+    // SAFETY: forwarded to the caller
+    let inner = unsafe { attach(ptr, len)? };
+
+    Ok(Sender { inner })
+}
+
+/// Attaches the receiver side to the channel in the region of `len` bytes at `ptr`
+///
+/// # Safety
+/// - `ptr` must point to a region of `len` bytes that is valid for reads and writes for `'a`,
+///   that `init` may have initialized for elements of type `T` and that no other receiver is
+///   attached to
+/// - Values of type `T` must remain valid when they are moved from the sender's side, e.g. they
+///   must not hold pointers into the sender's address space
+pub unsafe fn attach_receiver<'a, T>(
+    ptr: NonNull<u8>,
+    len: usize,
+) -> Result<Receiver<'a, T>, AttachError> {
+    // SAFETY: forwarded to the caller
+    let inner = unsafe { attach(ptr, len)? };
+
+    Ok(Receiver { inner })
+}
+
+/// The error returned when attaching to a region fails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AttachError {
+    /// The region is not aligned to `ALIGN`
+    Misaligned,
+    /// The region is smaller than its header or than the capacity recorded in it requires
+    TooSmall,
+    /// The region has not been initialized
+    BadMagic,
+    /// The region was initialized with another layout version
+    BadVersion,
+    /// The region was initialized for elements of another size or alignment
+    BadElement,
+    /// The capacity recorded in the region is zero or larger than `spsc::MAX_CAPACITY`
+    BadCapacity,
+}
+
+/// # Safety
+/// - See `attach_sender`
+unsafe fn attach<'a, T>(ptr: NonNull<u8>, len: usize) -> Result<Inner<'a, T>, AttachError> {
+    if !ptr.cast::<SharedHeader>().is_aligned() {
+        return Err(AttachError::Misaligned);
+    }
+
+    if len < mem::size_of::<SharedHeader>() {
+        return Err(AttachError::TooSmall);
+    }
+
+    // SAFETY: the caller ensures `ptr` is valid for reads; the magic is checked before any other
+    // field is read
+    let header = unsafe { ptr.cast::<SharedHeader>().as_ref() };
+
+    // Acquire: synchronizes with the Release `magic` store in `init`
+    if header.magic.load(atomic::Ordering::Acquire) != MAGIC {
+        return Err(AttachError::BadMagic);
+    }
+
+    if header.version != VERSION {
+        return Err(AttachError::BadVersion);
+    }
+
+    if header.element_size as usize != mem::size_of::<T>()
+        || header.element_align as usize != mem::align_of::<T>()
+    {
+        return Err(AttachError::BadElement);
+    }
+
+    let capacity = header.capacity as usize;
+    if capacity == 0 || capacity > super::MAX_CAPACITY {
+        return Err(AttachError::BadCapacity);
+    }
+
+    let required = capacity
+        .checked_mul(mem::size_of::<T>())
+        .and_then(|size| size.checked_add(slots_offset::<T>()));
+    if required.is_none_or(|required| len < required) {
+        return Err(AttachError::TooSmall);
+    }
+
+    // SAFETY: the region holds `capacity` elements past the slots offset, which is aligned for
+    // `T`; `T` has the same layout as `UnsafeCell<MaybeUninit<T>>`
+    let buf =
+        unsafe { slice::from_raw_parts(ptr.as_ptr().add(slots_offset::<T>()).cast(), capacity) };
+
+    Ok(Inner {
+        header: &header.header,
+        buf,
+        // a waker is only meaningful within a single process
+        wakers: None,
+    })
+}
+
+const fn slots_offset<T>() -> usize {
+    mem::size_of::<SharedHeader>().next_multiple_of(mem::align_of::<T>())
+}
+
+// the layout is documented in the module documentation
+const _: () = {
+    assert!(mem::offset_of!(SharedHeader, capacity) == 16);
+    assert!(mem::offset_of!(SharedHeader, header) == 64);
+    assert!(mem::size_of::<SharedHeader>() == 256);
+};
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use crate::spsc::{TryRecvError, TrySendError};
+
+    use super::*;
+
+    #[repr(C, align(64))]
+    struct Region([u8; 512]);
+
+    #[test]
+    fn attach_validates_the_header() {
+        let mut region = Region([0; 512]);
+        let ptr = NonNull::from(&mut region).cast::<u8>();
+
+        // SAFETY: `region` is valid and aligned
+        unsafe {
+            assert_eq!(
+                Some(AttachError::BadMagic),
+                attach_sender::<u64>(ptr, 512).err()
+            );
+            assert_eq!(
+                Some(AttachError::Misaligned),
+                attach_sender::<u64>(ptr.add(8), 504).err()
+            );
+            assert_eq!(
+                Some(AttachError::TooSmall),
+                attach_sender::<u64>(ptr, 128).err()
+            );
+
+            assert_eq!(32, init::<u64>(ptr, 512));
+            assert_eq!(
+                Some(AttachError::BadElement),
+                attach_sender::<u32>(ptr, 512).err()
+            );
+            assert_eq!(
+                Some(AttachError::TooSmall),
+                attach_sender::<u64>(ptr, 511).err()
+            );
+
+            let sender = attach_sender::<u64>(ptr, 512).unwrap();
+            let receiver = attach_receiver::<u64>(ptr, 512).unwrap();
+            assert_eq!(32, sender.capacity());
+            assert_eq!(Ok(()), sender.send(42));
+            assert_eq!(Ok(42), receiver.recv());
+
+            drop(sender);
+            assert!(receiver.is_closed());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn two_processes() {
+        const CAPACITY: usize = 16;
+        const MESSAGES: u64 = 10_000;
+
+        let size = size_for::<u64>(CAPACITY);
+
+        // SAFETY: FFI calls whose results are checked; each process maps the region for as long
+        // as it uses its part of the channel
+        unsafe {
+            let fd = libc::memfd_create(c"fika-spsc".as_ptr(), 0);
+            assert!(fd >= 0);
+            assert_eq!(0, libc::ftruncate(fd, size as libc::off_t));
+
+            let map = || {
+                let addr = libc::mmap(
+                    ptr::null_mut(),
+                    size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    0,
+                );
+                (addr != libc::MAP_FAILED).then(|| NonNull::new_unchecked(addr).cast::<u8>())
+            };
+
+            let sender_region = map().unwrap();
+            assert_eq!(CAPACITY, init::<u64>(sender_region, size));
+
+            let pid = libc::fork();
+            assert!(pid >= 0);
+
+            if pid == 0 {
+                // the child must not return into the test harness so it reports through its exit
+                // status instead of panicking
+                let receiver = map().and_then(|region| attach_receiver::<u64>(region, size).ok());
+                let ok = match receiver {
+                    Some(receiver) => {
+                        let mut expected = 0;
+                        loop {
+                            match receiver.recv() {
+                                Ok(value) if value == expected => expected += 1,
+                                Ok(_) => break false,
+                                Err(TryRecvError::Empty) => {
+                                    libc::sched_yield();
+                                }
+                                Err(TryRecvError::Disconnected) => break expected == MESSAGES,
+                            }
+                        }
+                    }
+                    None => false,
+                };
+                libc::_exit(if ok { 0 } else { 1 });
+            }
+
+            let sender = attach_sender::<u64>(sender_region, size).unwrap();
+            for value in 0..MESSAGES {
+                let mut value = value;
+                while let Err(TrySendError::Full(full)) = sender.send(value) {
+                    value = full;
+                    libc::sched_yield();
+                }
+            }
+            drop(sender);
+
+            let mut status = 0;
+            assert_eq!(pid, libc::waitpid(pid, &mut status, 0));
+            assert!(libc::WIFEXITED(status));
+            assert_eq!(0, libc::WEXITSTATUS(status));
+
+            assert_eq!(0, libc::munmap(sender_region.as_ptr().cast(), size));
+            assert_eq!(0, libc::close(fd));
+        }
+    }
+}
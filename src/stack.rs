@@ -0,0 +1,198 @@
+//! A fixed-capacity, lock-free, multi-producer, multi-consumer stack of values
+//!
+//! Values are kept in internal slots which move between two Treiber stacks: one that holds the
+//! values and a free list of empty slots. The Treiber stacks order the accesses to a slot so a
+//! value written before `push` is visible to the thread that `pop`s it
+//!
+//! There's no Treiber-based counterpart for first-in first-out order: a Treiber stack only gives
+//! access to its top. Use `mpmc::Channel`, a bounded queue of values that can also live in a
+//! `static`, instead
+//!
+//! Currently only ARM is supported
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{self, AtomicUsize};
+
+use crate::treiber::{self, OwningNodePtr};
+
+type Slot<T> = MaybeUninit<T>;
+
+/// A fixed-capacity, lock-free, multi-producer, multi-consumer stack of values
+///
+/// Values left in the stack are never dropped
+pub struct Stack<T, const N: usize>
+where
+    T: 'static,
+{
+    values: treiber::Stack<Slot<T>>,
+    free: treiber::Stack<Slot<T>>,
+    /// The number of slots that have been taken out of `slots`; the rest have never been used
+    taken: AtomicUsize,
+    slots: [UnsafeCell<treiber::Node<Slot<T>>>; N],
+}
+
+impl<T, const N: usize> Stack<T, N> {
+    /// Creates a new, empty stack
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const {
+            assert!(N > 0, "capacity must be at least one");
+        }
+
+        Self {
+            values: treiber::Stack::new(),
+            free: treiber::Stack::new(),
+            taken: AtomicUsize::new(0),
+            slots: [const { UnsafeCell::new(treiber::Node::new(MaybeUninit::uninit())) }; N],
+        }
+    }
+
+    /// Pushes a value onto the stack
+    ///
+    /// Returns an `Err` if the stack is observed as being full
+    pub fn push(&'static self, value: T) -> Result<(), T> {
+        let Some(mut slot) = self.free.pop().or_else(|| self.take_slot()) else {
+            return Err(value);
+        };
+
+        slot.write(value);
+        self.values.push_unchecked(slot);
+
+        Ok(())
+    }
+
+    /// Pops the value that was pushed last
+    ///
+    /// Returns `None` if the stack is observed as being empty
+    pub fn pop(&'static self) -> Option<T> {
+        let slot = self.values.pop()?;
+
+        // SAFETY: slots in `values` hold a value, which is moved out before the slot is freed
+        let value = unsafe { slot.assume_init_read() };
+        self.free.push_unchecked(slot);
+
+        Some(value)
+    }
+
+    /// Returns the total number of elements the stack can hold
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Takes a slot that has never been used
+    fn take_slot(&'static self) -> Option<OwningNodePtr<Slot<T>>> {
+        let mut taken = self.taken.load(atomic::Ordering::Relaxed);
+
+        loop {
+            if taken == N {
+                return None;
+            }
+
+            match self.taken.compare_exchange_weak(
+                taken,
+                taken + 1,
+                atomic::Ordering::Relaxed,
+                atomic::Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => taken = current,
+            }
+        }
+
+        // SAFETY: each slot is taken out of `slots` once so this is the only reference to it;
+        // from here on it's only accessed through the treiber stacks
+        let slot = unsafe { &mut *self.slots[taken].get() };
+
+        Some(OwningNodePtr::new(slot))
+    }
+}
+
+// SAFETY: if you put the `Stack` in a static then you can move values between threads, therefore
+// the values must be `Send`
+unsafe impl<T, const N: usize> Sync for Stack<T, N> where T: Send {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifo_order() {
+        static STACK: Stack<i32, 2> = Stack::new();
+
+        assert_eq!(None, STACK.pop());
+        assert_eq!(Ok(()), STACK.push(1));
+        assert_eq!(Ok(()), STACK.push(2));
+        assert_eq!(Err(3), STACK.push(3));
+        assert_eq!(Some(2), STACK.pop());
+        assert_eq!(Ok(()), STACK.push(4));
+        assert_eq!(Some(4), STACK.pop());
+        assert_eq!(Some(1), STACK.pop());
+        assert_eq!(None, STACK.pop());
+    }
+
+    #[test]
+    fn slots_are_recycled() {
+        static STACK: Stack<usize, 3> = Stack::new();
+
+        for lap in 0..10 {
+            for value in 0..3 {
+                assert_eq!(Ok(()), STACK.push(lap * 3 + value));
+            }
+            assert!(STACK.push(0).is_err());
+
+            for value in (0..3).rev() {
+                assert_eq!(Some(lap * 3 + value), STACK.pop());
+            }
+        }
+    }
+
+    #[test]
+    fn many_producers_and_consumers() {
+        const THREADS: usize = 3;
+        const MESSAGES: usize = 1_000;
+
+        static STACK: Stack<usize, 4> = Stack::new();
+        static POPPED: AtomicUsize = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for value in 0..MESSAGES {
+                        let mut value = value;
+                        while let Err(v) = STACK.push(value) {
+                            value = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+
+                s.spawn(|| {
+                    let mut popped = 0;
+                    while popped < MESSAGES {
+                        if STACK.pop().is_some() {
+                            popped += 1;
+                        } else {
+                            std::thread::yield_now();
+                        }
+                    }
+                    POPPED.fetch_add(popped, atomic::Ordering::Relaxed);
+                });
+            }
+        });
+
+        assert_eq!(THREADS * MESSAGES, POPPED.load(atomic::Ordering::Relaxed));
+        assert_eq!(None, STACK.pop());
+    }
+
+    #[test]
+    fn check_stack_is_sync() {
+        is_sync::<Stack<i32, 2>>();
+    }
+
+    fn is_sync<T>()
+    where
+        T: Sync,
+    {
+    }
+}
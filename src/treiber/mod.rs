@@ -6,6 +6,8 @@ use core::arch::asm;
 use core::ptr::NonNull;
 use core::sync::atomic;
 use core::sync::atomic::AtomicPtr;
+#[cfg(feature = "debug-pools")]
+use core::sync::atomic::AtomicU8;
 use core::{ops, ptr};
 
 pub(crate) struct Stack<T> {
@@ -19,7 +21,18 @@ impl<T> Stack<T> {
         }
     }
 
-    pub fn push(&self, mut node: OwningNodePtr<T>) {
+    pub fn push(&self, node: OwningNodePtr<T>) {
+        #[cfg(feature = "debug-pools")]
+        if !self.check_release(&node) {
+            return;
+        }
+
+        self.push_unchecked(node);
+    }
+
+    /// Pushes `node` without the `debug-pools` checks, which assume a node belongs to a single
+    /// stack, so that nodes can move between stacks
+    pub fn push_unchecked(&self, mut node: OwningNodePtr<T>) {
         // XXX this feels iffy and sort of gives the impression that `self` needs to be pinned?
         let top_addr = NonNull::from(&self.top).cast::<usize>();
 
@@ -27,8 +40,6 @@ impl<T> Stack<T> {
             // SAFETY: non-null value
             let top = unsafe { load_link(top_addr) };
 
-            // NOTE ordering is not important as the data dependency will maintain the order of
-            // the operations
             // SAFETY: `node` is a valid pointer
             unsafe {
                 node.inner
@@ -37,6 +48,11 @@ impl<T> Stack<T> {
                     .store(top as *mut _, atomic::Ordering::Relaxed);
             }
 
+            // Release: the writes to the node, `next` and its data, that PRECEDE this barrier
+            // cannot be reordered to AFTER the store that publishes the node; pairs with the fence
+            // in `pop`
+            atomic::fence(atomic::Ordering::Release);
+
             // SAFETY: `node` is a valid pointer
             if unsafe { store_conditional(top_addr, node.inner.addr().get()).is_ok() } {
                 break;
@@ -53,12 +69,23 @@ impl<T> Stack<T> {
             let top = unsafe { load_link(top_addr) };
 
             if let Some(top) = NonNull::new(top as *mut Node<T>) {
+                // Acquire: the reads of the node, `next` and, once popped, its data, that FOLLOW
+                // this barrier cannot be reordered to BEFORE the load that observed the node;
+                // pairs with the fence in `push_unchecked`
+                atomic::fence(atomic::Ordering::Acquire);
+
                 // SAFETY: given that is non-null, `top` is a valid pointer as only valid
                 // pointers can be `push`-ed
                 let next = unsafe { top.as_ref().next.load(atomic::Ordering::Relaxed) };
 
                 // SAFETY: `top_addr` is a valid pointer
                 if unsafe { store_conditional(top_addr, next as usize).is_ok() } {
+                    #[cfg(feature = "debug-pools")]
+                    // SAFETY: `top` is a valid pointer
+                    unsafe {
+                        top.as_ref().state.store(IN_USE, atomic::Ordering::Relaxed);
+                    }
+
                     break Some(OwningNodePtr { inner: top });
                 } else {
                     continue 'retry;
@@ -72,6 +99,41 @@ impl<T> Stack<T> {
     }
 }
 
+#[cfg(feature = "debug-pools")]
+impl<T> Stack<T> {
+    /// Marks `node` as free and returns `false` if it must not be pushed onto this stack
+    ///
+    /// `push` does this check; pools call it before destroying the contents of a slot, and then
+    /// use `push_unchecked`, so that a double free does not run the destructor twice
+    pub fn check_release(&self, node: &OwningNodePtr<T>) -> bool {
+        use crate::debug_pools::{self, Violation};
+
+        // SAFETY: `node` is a valid pointer
+        let inner = unsafe { node.inner.as_ref() };
+        let slot = node.inner.addr().get();
+        let this = ptr::from_ref(self).cast_mut();
+
+        // the first push, i.e. `manage`, makes this stack the owner of the node
+        if let Err(owner) = inner.owner.compare_exchange(
+            ptr::null_mut(),
+            this,
+            atomic::Ordering::Relaxed,
+            atomic::Ordering::Relaxed,
+        ) && owner != this
+        {
+            debug_pools::report(Violation::ForeignSlot { slot });
+            return false;
+        }
+
+        if inner.state.swap(FREE, atomic::Ordering::Relaxed) == FREE {
+            debug_pools::report(Violation::DoubleFree { slot });
+            return false;
+        }
+
+        true
+    }
+}
+
 // SAFETY: if you put the `Stack` in a static then you can move nodes between threads, therefore
 // the data must be `Send`
 unsafe impl<T> Sync for Stack<T> where T: Send {}
@@ -152,6 +214,10 @@ impl<T> ops::Deref for SharedNodePtr<T> {
 
 pub(crate) struct Node<T> {
     next: AtomicPtr<Node<T>>,
+    #[cfg(feature = "debug-pools")]
+    owner: AtomicPtr<Stack<T>>,
+    #[cfg(feature = "debug-pools")]
+    state: AtomicU8,
     pub data: T,
 }
 
@@ -159,19 +225,30 @@ impl<T> Node<T> {
     pub const fn new(data: T) -> Self {
         Self {
             next: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "debug-pools")]
+            owner: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "debug-pools")]
+            state: AtomicU8::new(UNMANAGED),
             data,
         }
     }
 }
 
-fn clear_load_link() {
+#[cfg(feature = "debug-pools")]
+const UNMANAGED: u8 = 0;
+#[cfg(feature = "debug-pools")]
+const FREE: u8 = 1;
+#[cfg(feature = "debug-pools")]
+const IN_USE: u8 = 2;
+
+pub(crate) fn clear_load_link() {
     // SAFETY: cannot trigger undefined behavior
     unsafe { asm!("CLREX", options(nomem, nostack)) }
 }
 
 /// # Safety
 /// - `ptr` must be a valid pointer
-unsafe fn load_link(ptr: NonNull<usize>) -> usize {
+pub(crate) unsafe fn load_link(ptr: NonNull<usize>) -> usize {
     let value;
     // SAFETY: `ptr` is a valid pointer as per the caller contract
     unsafe {
@@ -186,7 +263,7 @@ unsafe fn load_link(ptr: NonNull<usize>) -> usize {
 
 /// # Safety
 /// - `ptr` must be a valid pointer
-unsafe fn store_conditional(ptr: NonNull<usize>, value: usize) -> Result<(), ()> {
+pub(crate) unsafe fn store_conditional(ptr: NonNull<usize>, value: usize) -> Result<(), ()> {
     let outcome: usize;
     // SAFETY: `ptr` is a valid pointer as per the caller contract
     unsafe {
@@ -268,4 +345,45 @@ mod tests {
         assert!(a.is_some());
         assert_eq!(value, *a.unwrap());
     }
+
+    #[cfg(feature = "debug-pools")]
+    #[test]
+    fn violations_are_reported_and_skipped() {
+        use core::sync::atomic::AtomicUsize;
+
+        use crate::debug_pools::{self, Violation};
+
+        static DOUBLE_FREES: AtomicUsize = AtomicUsize::new(0);
+        static FOREIGN_SLOTS: AtomicUsize = AtomicUsize::new(0);
+
+        let _guard = debug_pools::TEST_HOOK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        debug_pools::set_hook(|violation| match violation {
+            Violation::DoubleFree { .. } => {
+                DOUBLE_FREES.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+            Violation::ForeignSlot { .. } => {
+                FOREIGN_SLOTS.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+        });
+
+        let stack = Stack::new();
+        let a = OwningNodePtr::new(Box::leak(Box::new(Node::new(42))));
+        // SAFETY: deliberate misuse
+        let alias = unsafe { a.copy() };
+        stack.push(a);
+        stack.push(alias);
+        assert_eq!(1, DOUBLE_FREES.load(atomic::Ordering::Relaxed));
+
+        // the second push was skipped
+        let a = stack.pop();
+        assert!(a.is_some());
+        assert!(stack.pop().is_none());
+
+        let other = Stack::new();
+        other.push(a.unwrap());
+        assert_eq!(1, FOREIGN_SLOTS.load(atomic::Ordering::Relaxed));
+        assert!(other.pop().is_none());
+    }
 }
@@ -1,12 +1,13 @@
 //! A contiguous growable array type with fixed capacity backed by a byte buffer
 
 use core::marker::PhantomData;
+use core::mem::MaybeUninit;
 use core::{fmt, mem, ops, ptr, slice};
 
 /// A contiguous growable array type with fixed capacity backed by a byte buffer
 pub struct Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
 {
     data: PhantomData<T>,
     len: usize,
@@ -15,15 +16,24 @@ where
 
 impl<T, S> Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
 {
     /// Creates a new empty vector
+    ///
+    /// Elements are stored at the first address within `storage` that is aligned for `T`. When
+    /// the storage is `INLINE` this offset must not change as the vector moves, hence the storage
+    /// must be at least as aligned as `T`, e.g. `AlignedBytes<T, _>`; other storage, e.g.
+    /// `&mut [u8]` or `Bytes`, is aligned at runtime
     pub const fn new(storage: S) -> Self {
         const {
             assert!(
                 0 != mem::size_of::<T>(),
                 "zero-sized types are currently not supported"
             );
+            assert!(
+                !S::INLINE || mem::align_of::<S>() >= mem::align_of::<T>(),
+                "inline storage must be at least as aligned as the elements"
+            );
         }
 
         Self {
@@ -65,11 +75,11 @@ where
 
     /// Returns the total number of elements the vector can hold
     pub fn capacity(&self) -> usize {
-        let storage = self.storage.as_ref();
+        let storage = self.storage.as_uninit_bytes();
         let addr = storage.as_ptr() as usize;
         let len = storage.len();
 
-        let align = mem::size_of::<T>();
+        let align = mem::align_of::<T>();
         let offset = addr % align;
         let adj = if offset == 0 { 0 } else { align - offset };
 
@@ -83,10 +93,10 @@ where
     /// # Safety
     /// - This is allowed to point outside `storage` so a length check must be performed first
     unsafe fn aligned_storage_ptr(&self) -> *const T {
-        let storage = self.storage.as_ref();
+        let storage = self.storage.as_uninit_bytes();
         let ptr = storage.as_ptr();
 
-        let align = mem::size_of::<T>();
+        let align = mem::align_of::<T>();
         let offset = ptr as usize % align;
         let adj = if offset == 0 { 0 } else { align - offset };
 
@@ -95,9 +105,185 @@ where
     }
 }
 
+/// A byte buffer that can back a `Vec`
+///
+/// The bytes need not be initialized
+///
+/// # Safety
+/// - Both methods must return the same memory region every time they are called, as long as `self`
+///   is not moved
+/// - Moving `self` must not invalidate the contents of the region
+/// - If `INLINE` is `false`, moving `self` must not move the region; otherwise the region must
+///   move together with `self`, i.e. stay at the same offset from it
+pub unsafe trait Storage {
+    /// Whether the region is stored inline and so moves together with `self`, e.g. an array
+    ///
+    /// Storage that points to a region elsewhere, e.g. a reference or a pool handle, sets this to
+    /// `false`
+    const INLINE: bool = true;
+
+    /// Returns the buffer as a slice of possibly uninitialized bytes
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>];
+
+    /// Returns the buffer as a mutable slice of possibly uninitialized bytes
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>];
+}
+
+// SAFETY: a slice always refers to the same memory
+unsafe impl Storage for [u8] {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        // SAFETY: `MaybeUninit<u8>` has the same layout as `u8`
+        unsafe { &*(ptr::from_ref(self) as *const [MaybeUninit<u8>]) }
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        // SAFETY: `MaybeUninit<u8>` has the same layout as `u8`
+        unsafe { &mut *(ptr::from_mut(self) as *mut [MaybeUninit<u8>]) }
+    }
+}
+
+// SAFETY: a slice always refers to the same memory
+unsafe impl Storage for [MaybeUninit<u8>] {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        self
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self
+    }
+}
+
+// SAFETY: delegates to the slice implementation; the array is moved together with its contents
+unsafe impl<const N: usize> Storage for [u8; N] {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        self[..].as_uninit_bytes()
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self[..].as_uninit_bytes_mut()
+    }
+}
+
+// SAFETY: the array is moved together with its contents
+unsafe impl<const N: usize> Storage for [MaybeUninit<u8>; N] {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        self
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self
+    }
+}
+
+// SAFETY: the array is moved together with its contents
+unsafe impl<const N: usize> Storage for MaybeUninit<[u8; N]> {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        // SAFETY: `MaybeUninit<[u8; N]>` has the same layout as `[MaybeUninit<u8>; N]`
+        unsafe { &*ptr::from_ref(self).cast::<[MaybeUninit<u8>; N]>() }
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        // SAFETY: `MaybeUninit<[u8; N]>` has the same layout as `[MaybeUninit<u8>; N]`
+        unsafe { &mut *ptr::from_mut(self).cast::<[MaybeUninit<u8>; N]>() }
+    }
+}
+
+// SAFETY: moving the reference does not move the memory it points to
+unsafe impl<S> Storage for &mut S
+where
+    S: Storage + ?Sized,
+{
+    const INLINE: bool = false;
+
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        S::as_uninit_bytes(self)
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        S::as_uninit_bytes_mut(self)
+    }
+}
+
+/// Adapts a byte buffer that implements `AsRef<[u8]>` and `AsMut<[u8]>` into `Storage`
+///
+/// The region the buffer refers to must stay in place when the buffer moves, e.g. a heap
+/// allocation or a reference, so the region is aligned at runtime and can hold elements of any
+/// alignment. Byte arrays move their contents; use `AlignedBytes` or a reference to the array
+/// instead
+pub struct Bytes<B> {
+    buffer: B,
+}
+
+impl<B> Bytes<B>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Wraps `buffer`
+    ///
+    /// # Safety
+    /// - `buffer.as_ref()` and `buffer.as_mut()` must return the same memory region every time
+    ///   they are called
+    /// - Moving `buffer` must not move that region nor invalidate its contents
+    pub const unsafe fn new(buffer: B) -> Self {
+        Self { buffer }
+    }
+
+    /// Returns the wrapped buffer
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+// SAFETY: `Bytes::new` is unsafe and its caller upholds the `Storage` contract for `B`
+unsafe impl<B> Storage for Bytes<B>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    const INLINE: bool = false;
+
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        self.buffer.as_ref().as_uninit_bytes()
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.buffer.as_mut().as_uninit_bytes_mut()
+    }
+}
+
+/// An inline byte buffer of `N` bytes that is aligned for `T`
+///
+/// Unlike a plain byte array, it can back a `Vec` of elements that are more aligned than a byte
+#[repr(C)]
+pub struct AlignedBytes<T, const N: usize> {
+    align: [T; 0],
+    bytes: [MaybeUninit<u8>; N],
+}
+
+impl<T, const N: usize> AlignedBytes<T, N> {
+    /// Creates an uninitialized buffer
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            align: [],
+            bytes: [MaybeUninit::uninit(); N],
+        }
+    }
+}
+
+// SAFETY: the array is moved together with its contents
+unsafe impl<T, const N: usize> Storage for AlignedBytes<T, N> {
+    fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+        &self.bytes
+    }
+
+    fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        &mut self.bytes
+    }
+}
+
 impl<T, S> fmt::Debug for Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
     T: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -105,9 +291,130 @@ where
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<T, S> defmt::Format for Vec<T, S>
+where
+    S: Storage,
+    T: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        <[T]>::format(self, f)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T, S> ufmt::uDebug for Vec<T, S>
+where
+    S: Storage,
+    T: ufmt::uDebug,
+{
+    fn fmt<W>(&self, f: &mut ufmt::Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: ufmt::uWrite + ?Sized,
+    {
+        <[T]>::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, S> serde::Serialize for Vec<T, S>
+where
+    S: Storage,
+    T: serde::Serialize,
+{
+    fn serialize<SE>(&self, serializer: SE) -> Result<SE::Ok, SE::Error>
+    where
+        SE: serde::Serializer,
+    {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Deserializes a sequence into the storage of this vector, appending to its current contents
+///
+/// Returns an error if the sequence does not fit in the remaining capacity
+#[cfg(feature = "serde")]
+impl<'de, T, S> serde::de::DeserializeSeed<'de> for Vec<T, S>
+where
+    S: Storage,
+    T: serde::Deserialize<'de>,
+{
+    type Value = Self;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SeqVisitor { vec: self })
+    }
+}
+
+/// Deserializes a sequence into default-initialized storage
+///
+/// Returns an error if the sequence does not fit in the capacity of the storage
+#[cfg(feature = "serde")]
+impl<'de, T, S> serde::Deserialize<'de> for Vec<T, S>
+where
+    S: Storage + Default,
+    T: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde::de::DeserializeSeed::deserialize(Vec::new(S::default()), deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct SeqVisitor<T, S>
+where
+    S: Storage,
+{
+    vec: Vec<T, S>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, S> serde::de::Visitor<'de> for SeqVisitor<T, S>
+where
+    S: Storage,
+    T: serde::Deserialize<'de>,
+{
+    type Value = Vec<T, S>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let remaining = self.vec.capacity() - self.vec.len();
+        write!(f, "a sequence of at most {remaining} elements")
+    }
+
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        use serde::de::Error;
+
+        let remaining = self.vec.capacity() - self.vec.len();
+        if let Some(len) = seq.size_hint()
+            && len > remaining
+        {
+            return Err(A::Error::invalid_length(len, &self));
+        }
+
+        let mut len = 0;
+        while let Some(element) = seq.next_element()? {
+            len += 1;
+            if self.vec.push(element).is_err() {
+                return Err(A::Error::invalid_length(len, &self));
+            }
+        }
+
+        Ok(self.vec)
+    }
+}
+
 impl<T, S> ops::Deref for Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
 {
     type Target = [T];
 
@@ -119,7 +426,7 @@ where
 
 impl<T, S> ops::DerefMut for Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         // SAFETY: `len` is trusted given that it's only changed by safe API
@@ -129,7 +436,7 @@ where
 
 impl<T, S> Drop for Vec<T, S>
 where
-    S: AsRef<[u8]> + AsMut<[u8]>,
+    S: Storage,
 {
     fn drop(&mut self) {
         // SAFETY:
@@ -231,6 +538,153 @@ mod tests {
         assert_eq!(2, DESTROYED.load(atomic::Ordering::Relaxed));
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_into_storage() {
+        use serde::de::DeserializeSeed;
+        use serde::de::value::{Error, SeqDeserializer};
+
+        let mut storage = [0; 4];
+        let mut vec = Vec::<u8, _>::new(&mut storage[..]);
+        assert!(vec.push(1).is_ok());
+
+        let deserializer = SeqDeserializer::<_, Error>::new([2u8, 3].into_iter());
+        let vec = vec.deserialize(deserializer).unwrap();
+        assert_eq!([1, 2, 3], *vec);
+
+        let deserializer = SeqDeserializer::<_, Error>::new([4u8, 5].into_iter());
+        assert!(vec.deserialize(deserializer).is_err());
+    }
+
+    #[cfg(feature = "ufmt")]
+    #[test]
+    fn udebug() {
+        struct Writer(std::string::String);
+
+        impl ufmt::uWrite for Writer {
+            type Error = core::convert::Infallible;
+
+            fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+                self.0.push_str(s);
+                Ok(())
+            }
+        }
+
+        let mut vec = Vec::new([0; 4]);
+        assert!(vec.push(1u8).is_ok());
+        assert!(vec.push(2).is_ok());
+
+        let mut writer = Writer(std::string::String::new());
+        ufmt::uwrite!(writer, "{:?}", vec).unwrap();
+        assert_eq!("[1, 2]", writer.0);
+    }
+
+    #[test]
+    fn backed_by_uninit_storage() {
+        let mut storage = MaybeUninit::<[u8; 4]>::uninit();
+        let mut vec = Vec::new(&mut storage);
+        assert_eq!(4, vec.capacity());
+
+        assert!(vec.push(1u8).is_ok());
+        assert!(vec.push(2).is_ok());
+        assert_eq!([1, 2], &*vec);
+
+        let storage = [MaybeUninit::uninit(); 4];
+        let mut vec = Vec::new(storage);
+        assert!(vec.push(3u8).is_ok());
+        assert_eq!([3], &*vec);
+    }
+
+    #[test]
+    fn moving_inline_storage_keeps_the_contents() {
+        #[repr(align(4))]
+        struct Words([u8; 16]);
+
+        // SAFETY: the array is moved together with its contents
+        unsafe impl Storage for Words {
+            fn as_uninit_bytes(&self) -> &[MaybeUninit<u8>] {
+                self.0.as_uninit_bytes()
+            }
+
+            fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+                self.0.as_uninit_bytes_mut()
+            }
+        }
+
+        #[inline(never)]
+        fn construct_and_move() -> Vec<u32, Words> {
+            let mut vec = Vec::new(Words([0; 16]));
+            assert!(vec.push(1).is_ok());
+            assert!(vec.push(2).is_ok());
+
+            vec
+        }
+
+        let vec = Box::new(construct_and_move());
+        assert_eq!(4, vec.capacity());
+        assert_eq!([1, 2], **vec);
+    }
+
+    #[test]
+    fn backed_by_as_ref_buffer() {
+        // SAFETY: a `Vec<u8>` always derefs to the same heap buffer, which stays in place when
+        // the `Vec<u8>` moves
+        let storage = unsafe { Bytes::new(std::vec![0u8; 9]) };
+        let mut vec = Vec::new(storage);
+
+        // a heap buffer of bytes is only guaranteed to be aligned for bytes
+        let capacity = vec.capacity();
+        assert!(capacity == 1 || capacity == 2);
+        for value in 0..capacity as u32 {
+            assert!(vec.push(value).is_ok());
+        }
+        assert!(vec.push(0).is_err());
+
+        let vec = Box::new(vec);
+        assert_eq!((0..capacity as u32).collect::<std::vec::Vec<_>>(), **vec);
+    }
+
+    #[test]
+    fn backed_by_aligned_bytes() {
+        #[inline(never)]
+        fn construct_and_move() -> Vec<u64, AlignedBytes<u64, 16>> {
+            let mut vec = Vec::new(AlignedBytes::new());
+            assert!(vec.push(1).is_ok());
+            assert!(vec.push(2).is_ok());
+            assert!(vec.push(3).is_err());
+
+            vec
+        }
+
+        let vec = Box::new(construct_and_move());
+        assert_eq!(2, vec.capacity());
+        assert_eq!([1, 2], **vec);
+    }
+
+    #[test]
+    fn backed_by_box_pool() {
+        use crate::box_pool::{BoxPool, Slot};
+
+        static POOL: BoxPool<[u8; 8]> = BoxPool::new();
+
+        POOL.manage(Box::leak(Box::new(Slot::new())));
+
+        let storage = POOL.request([0; 8]).ok().unwrap();
+        let mut vec = Vec::new(storage);
+        assert!(vec.push(1u8).is_ok());
+        assert_eq!([1], &*vec);
+
+        assert!(
+            POOL.request([0; 8]).is_err(),
+            "expected pool to be exhausted"
+        );
+
+        // returns storage to the pool
+        drop(vec);
+
+        assert!(POOL.request([0; 8]).is_ok(), "expected pool to have a box");
+    }
+
     #[test]
     fn backed_by_pool() {
         const ALLOC_SIZE: usize = 128;
@@ -0,0 +1,234 @@
+//! Strategies to wait for the other side of a channel to make progress
+//!
+//! The blocking channel operations retry in a loop and call `Wait::wait` in between; once they
+//! succeed they call `Wait::notify` so that a peer that waits with the same strategy notices the
+//! progress. Waiting may return spuriously: the operation checks the channel again anyway
+//!
+//! A strategy that provides a `Wait::waker` is also woken when the other side is dropped; the
+//! blocking operations register the waker with the channel before they wait
+
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+use core::hint;
+use core::task::Waker;
+
+/// A strategy to wait for the other side of a channel to make progress
+pub trait Wait {
+    /// Waits until the other side may have made progress
+    ///
+    /// Returning early is fine but never returning, when the other side never notifies, is not
+    fn wait(&mut self);
+
+    /// Tells the other side that this side made progress
+    fn notify(&mut self) {}
+
+    /// Returns a waker that ends a `wait` of the current thread, if the strategy has one
+    ///
+    /// Strategies without one must return from `wait` on their own to notice that the other side
+    /// was dropped, e.g. by spinning
+    fn waker(&mut self) -> Option<&Waker> {
+        None
+    }
+}
+
+/// Busy waits with a hint to the processor
+pub struct Spin;
+
+impl Wait for Spin {
+    fn wait(&mut self) {
+        hint::spin_loop();
+    }
+}
+
+/// Busy waits for exponentially longer periods, up to a limit
+pub struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    const MAX_STEP: u32 = 6;
+
+    /// Creates a backoff that starts with the shortest period
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self { step: 0 }
+    }
+}
+
+impl Wait for Backoff {
+    fn wait(&mut self) {
+        for _ in 0..1 << self.step {
+            hint::spin_loop();
+        }
+
+        if self.step < Self::MAX_STEP {
+            self.step += 1;
+        }
+    }
+
+    fn notify(&mut self) {
+        // the channel moved so the next wait is likely short
+        self.step = 0;
+    }
+}
+
+/// Sleeps with the `WFE` instruction and wakes the peer with the `SEV` instruction
+///
+/// An interrupt also wakes the processor so a peer that runs in an interrupt handler does not need
+/// to use this strategy; a peer on another core does
+#[cfg(target_arch = "arm")]
+pub struct Wfe;
+
+#[cfg(target_arch = "arm")]
+impl Wait for Wfe {
+    fn wait(&mut self) {
+        // SAFETY: cannot trigger undefined behavior
+        unsafe { asm!("WFE", options(nomem, nostack)) }
+    }
+
+    fn notify(&mut self) {
+        sev();
+    }
+
+    fn waker(&mut self) -> Option<&Waker> {
+        Some(&SEV_WAKER)
+    }
+}
+
+#[cfg(target_arch = "arm")]
+fn sev() {
+    // SAFETY: cannot trigger undefined behavior
+    unsafe { asm!("SEV", options(nomem, nostack)) }
+}
+
+/// A waker that sends an event to every core
+#[cfg(target_arch = "arm")]
+static SEV_WAKER: Waker = {
+    use core::ptr;
+    use core::task::{RawWaker, RawWakerVTable};
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(ptr::null(), &VTABLE),
+        |_| sev(),
+        |_| sev(),
+        |_| {},
+    );
+
+    // SAFETY: the functions of `VTABLE` ignore the data pointer and are thread-safe
+    unsafe { Waker::new(ptr::null(), &VTABLE) }
+};
+
+/// Parks the current thread and unparks the peer's thread
+///
+/// The peer must wait with a `Park` that refers back to this thread; otherwise only dropping the
+/// peer unparks this thread
+#[cfg(feature = "std")]
+pub struct Park {
+    peer: std::thread::Thread,
+    /// Unparks the thread that waits; created on the first wait
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl Park {
+    /// Creates a strategy that unparks `peer`, the thread that runs the other side of the channel
+    pub fn new(peer: std::thread::Thread) -> Self {
+        Self { peer, waker: None }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Wait for Park {
+    fn wait(&mut self) {
+        // a notification that arrived after the last check is kept so this returns right away
+        std::thread::park();
+    }
+
+    fn notify(&mut self) {
+        self.peer.unpark();
+    }
+
+    fn waker(&mut self) -> Option<&Waker> {
+        Some(self.waker.get_or_insert_with(|| {
+            Waker::from(std::sync::Arc::new(Unpark(std::thread::current())))
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+struct Unpark(std::thread::Thread);
+
+#[cfg(feature = "std")]
+impl std::task::Wake for Unpark {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// A callback, e.g. one that runs other work or sleeps until the next interrupt
+impl<F> Wait for F
+where
+    F: FnMut(),
+{
+    fn wait(&mut self) {
+        self()
+    }
+}
+
+/// The point in time at which a blocking operation gives up
+pub trait Deadline {
+    /// Returns `true` once the deadline has passed
+    fn has_expired(&self) -> bool;
+}
+
+/// A callback that reads a clock, e.g. `|| now() >= deadline`
+impl<F> Deadline for F
+where
+    F: Fn() -> bool,
+{
+    fn has_expired(&self) -> bool {
+        self()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Deadline for std::time::Instant {
+    fn has_expired(&self) -> bool {
+        std::time::Instant::now() >= *self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_resets_on_notify() {
+        let mut backoff = Backoff::new();
+
+        for _ in 0..10 {
+            backoff.wait();
+        }
+        assert_eq!(Backoff::MAX_STEP, backoff.step);
+
+        backoff.notify();
+        assert_eq!(0, backoff.step);
+    }
+
+    #[test]
+    fn callback_deadline() {
+        let calls = core::cell::Cell::new(0);
+        let deadline = || {
+            calls.set(calls.get() + 1);
+            calls.get() > 2
+        };
+
+        assert!(!deadline.has_expired());
+        assert!(!deadline.has_expired());
+        assert!(deadline.has_expired());
+    }
+}
@@ -0,0 +1,114 @@
+//! A waker slot that one side registers into and the other side wakes, possibly concurrently
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{self, AtomicBool, AtomicU8};
+use core::task::Waker;
+
+const WAITING: u8 = 0;
+const REGISTERING: u8 = 0b01;
+const WAKING: u8 = 0b10;
+
+/// Holds the waker of a single task
+///
+/// `register` must not be called concurrently with itself; `wake` may be called from anywhere,
+/// e.g. from an interrupt handler that preempted `register`
+///
+/// `wake` is cheap when no waker is registered so it can be called on every send. In exchange,
+/// the task must check for the event it waits on *after* `register`
+pub(crate) struct AtomicWaker {
+    state: AtomicU8,
+    /// Whether `waker` holds a waker; only written while `state` grants access to `waker`
+    registered: AtomicBool,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+impl AtomicWaker {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(WAITING),
+            registered: AtomicBool::new(false),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Registers `waker` to be woken by the next `wake`
+    pub fn register(&self, waker: &Waker) {
+        // Acquire: the previous `register` and `wake` accesses to `waker` happen before ours
+        match self
+            .state
+            .compare_exchange(
+                WAITING,
+                REGISTERING,
+                atomic::Ordering::Acquire,
+                atomic::Ordering::Acquire,
+            )
+            .unwrap_or_else(|state| state)
+        {
+            WAITING => {
+                // SAFETY: the `REGISTERING` state grants exclusive access to `waker`
+                let slot = unsafe { &mut *self.waker.get() };
+                if !slot
+                    .as_ref()
+                    .is_some_and(|current| current.will_wake(waker))
+                {
+                    *slot = Some(waker.clone());
+                }
+                self.registered.store(true, atomic::Ordering::Relaxed);
+
+                // Release: our `waker` write happens before a `wake` takes it
+                if self
+                    .state
+                    .compare_exchange(
+                        REGISTERING,
+                        WAITING,
+                        atomic::Ordering::AcqRel,
+                        atomic::Ordering::Acquire,
+                    )
+                    .is_err()
+                {
+                    // a `wake` happened while we were registering and left the waking to us
+                    // SAFETY: `wake` does not touch `waker` while `REGISTERING` is set
+                    let waker = unsafe { (*self.waker.get()).take() };
+                    self.registered.store(false, atomic::Ordering::Relaxed);
+                    self.state.swap(WAITING, atomic::Ordering::AcqRel);
+
+                    if let Some(waker) = waker {
+                        waker.wake();
+                    }
+                }
+            }
+
+            // a `wake` is taking the previous waker; the task must be polled again
+            _ => waker.wake_by_ref(),
+        }
+
+        // SeqCst: pairs with the fence in `wake`; either `wake` observes `registered` or the
+        // caller's check for the event, which follows, observes the event
+        atomic::fence(atomic::Ordering::SeqCst);
+    }
+
+    /// Wakes the registered task, if any
+    pub fn wake(&self) {
+        // SeqCst: the caller's event, e.g. a `write` cursor store, happens before the
+        // `registered` load; pairs with the fence in `register`
+        atomic::fence(atomic::Ordering::SeqCst);
+        if !self.registered.load(atomic::Ordering::Relaxed) {
+            return;
+        }
+
+        // AcqRel: synchronizes with the `register` that stored the waker
+        if self.state.fetch_or(WAKING, atomic::Ordering::AcqRel) == WAITING {
+            // SAFETY: the `WAKING` state grants exclusive access to `waker`
+            let waker = unsafe { (*self.waker.get()).take() };
+            self.registered.store(false, atomic::Ordering::Relaxed);
+            self.state.fetch_and(!WAKING, atomic::Ordering::Release);
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+// SAFETY: the `state` protocol serializes the accesses to `waker` and `Waker` is `Send + Sync`
+unsafe impl Sync for AtomicWaker {}